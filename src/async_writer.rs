@@ -0,0 +1,286 @@
+//! # Asynchronous mode
+//! By default every record is rendered and written on the thread that logged it.
+//! In asynchronous mode, records are pushed into a bounded queue instead, and a dedicated writer thread
+//! does the formatting and the I/O of all the sinks.
+//!
+//! When the queue is full, the configured `OverflowPolicy` decides what happens with new records.
+//! Dropped records are counted and can be read with `dropped_records`.
+//!
+//! Records still in the queue are lost when the process exits, so call `disable_async` (or at least `flush_async`)
+//! before leaving `main`.
+//! ```
+//! use logger_rust::*;
+//!
+//! enable_async(AsyncConfig::new(1024, OverflowPolicy::DropOldest));
+//! for i in 0..10 {
+//!     log_info!("Written by the writer thread: {}", i);
+//! }
+//! disable_async(); // drains the queue and stops the writer thread
+//! assert_eq!(dropped_records(), 0);
+//! ```
+use crate::record::Record;
+use crate::sink::{dispatch, flush_sinks};
+use crate::error::{lock, report_error, try_lock, LoggerError};
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    sync::atomic::{AtomicU64, Ordering},
+    thread::{self, JoinHandle},
+};
+
+lazy_static::lazy_static! {
+    static ref QUEUE: Mutex<Queue> = Mutex::new(Queue::default());
+    static ref WRITER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+    static ref NOT_EMPTY: Condvar = Condvar::new();
+    static ref NOT_FULL: Condvar = Condvar::new();
+    static ref IDLE: Condvar = Condvar::new();
+}
+
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Set on the writer thread, so that records logged by sinks themselves are written synchronously
+    /// instead of waiting for room in the queue they are supposed to drain.
+    static IS_WRITER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// What to do with a new record when the queue is full.
+///
+/// ```
+/// use logger_rust::*;
+/// use std::io;
+/// use std::sync::{mpsc, Mutex};
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// /// Holds the writer thread on the `hold` record until `release` receives something.
+/// struct GateSink {
+///     entered: Mutex<mpsc::Sender<()>>,
+///     release: Mutex<mpsc::Receiver<()>>,
+/// }
+///
+/// impl Sink for GateSink {
+///     fn write(&self, record: &Record) -> io::Result<()> {
+///         if record.message == "hold" {
+///             self.entered.lock().unwrap().send(()).unwrap();
+///             self.release.lock().unwrap().recv().unwrap();
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let (entered, writer_held) = mpsc::channel();
+/// let (release, released) = mpsc::channel();
+/// add_sink(GateSink { entered: Mutex::new(entered), release: Mutex::new(released) });
+/// let capture = CaptureSink::new();
+/// add_sink(capture.clone());
+///
+/// // Logs `hold`, waits for the writer thread to be stuck on it, then logs 1 to 4 into a queue of 2
+/// let run = |overflow: OverflowPolicy| -> (Vec<String>, u64) {
+///     capture.clear();
+///     let dropped = dropped_records();
+///     enable_async(AsyncConfig::new(2, overflow));
+///     log_info!("hold");
+///     writer_held.recv().unwrap();
+///     let (done, logged) = mpsc::channel();
+///     let logger = thread::spawn(move || {
+///         (1..=4).for_each(|i| log_info!("{}", i));
+///         done.send(()).unwrap();
+///     });
+///     // `Block` keeps the logging thread waiting until the writer thread makes room
+///     let blocked = logged.recv_timeout(Duration::from_millis(200)).is_err();
+///     assert_eq!(blocked, overflow == OverflowPolicy::Block);
+///     release.send(()).unwrap();
+///     logger.join().unwrap();
+///     disable_async();
+///     let messages = capture.records().into_iter().map(|record| record.message).collect();
+///     (messages, dropped_records() - dropped)
+/// };
+///
+/// assert_eq!(run(OverflowPolicy::DropNewest), (vec!["hold".into(), "1".into(), "2".into()], 2));
+/// assert_eq!(run(OverflowPolicy::DropOldest), (vec!["hold".into(), "3".into(), "4".into()], 2));
+/// let (messages, dropped) = run(OverflowPolicy::Block);
+/// assert_eq!(messages, ["hold", "1", "2", "3", "4"]);
+/// assert_eq!(dropped, 0);
+/// # clear_sinks();
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Block the logging thread until the writer thread makes room.
+    Block,
+    /// Drop the new record.
+    DropNewest,
+    /// Drop the oldest record in the queue to make room for the new one.
+    DropOldest,
+}
+
+/// Configuration of the asynchronous mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AsyncConfig {
+    /// The maximum number of records waiting in the queue.
+    pub capacity: usize,
+    /// What to do when the queue is full.
+    pub overflow: OverflowPolicy,
+}
+
+impl AsyncConfig {
+    /// Creates a new `AsyncConfig`.
+    pub fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self { capacity, overflow }
+    }
+}
+
+impl Default for AsyncConfig {
+    fn default() -> Self {
+        Self::new(8192, OverflowPolicy::Block)
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    records: VecDeque<Record>,
+    config: AsyncConfig,
+    active: bool,
+    in_flight: usize,
+}
+
+/// Switches to asynchronous mode and starts the writer thread. A `capacity` of `0` is treated as `1`.
+/// If asynchronous mode is already enabled, only the configuration is updated.
+/// If the writer thread cannot be started, the error is reported and the records keep being written synchronously.
+pub fn enable_async(config: AsyncConfig) {
+    let config = AsyncConfig::new(config.capacity.max(1), config.overflow);
+    start(config, lock(&QUEUE), lock(&WRITER)).unwrap_or_else(report_error);
+}
+
+/// Same as `enable_async`, but returns an error instead of reporting it.
+/// Also fails with `LoggerError::InvalidConfig` if the capacity is zero (which `enable_async` raises to one),
+/// and with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_enable_async(config: AsyncConfig) -> Result<(), LoggerError> {
+    if config.capacity == 0 {
+        return Err(LoggerError::InvalidConfig("the capacity of the async queue must be at least 1".to_string()));
+    }
+    let queue = try_lock(&QUEUE, "async queue")?;
+    let writer = try_lock(&WRITER, "async writer")?;
+    start(config, queue, writer)
+}
+
+fn start(
+    config: AsyncConfig,
+    mut queue: MutexGuard<'_, Queue>,
+    mut writer: MutexGuard<'_, Option<JoinHandle<()>>>,
+) -> Result<(), LoggerError> {
+    queue.config = config;
+    if queue.active {
+        return Ok(());
+    }
+    if let Some(handle) = writer.take() {
+        // A previous writer is still finishing its last batch after `disable_async` was called
+        // from another thread; wait for it before starting a new one.
+        drop(queue);
+        let _ = handle.join();
+        queue = lock(&QUEUE);
+    }
+    let handle = thread::Builder::new()
+        .name("logger-rust-writer".to_string())
+        .spawn(run_writer)
+        .map_err(LoggerError::Spawn)?;
+    queue.active = true;
+    *writer = Some(handle);
+    Ok(())
+}
+
+/// Writes every queued record, stops the writer thread and switches back to synchronous mode.
+pub fn disable_async() {
+    lock(&QUEUE).active = false;
+    NOT_EMPTY.notify_all();
+    NOT_FULL.notify_all();
+    if let Some(handle) = lock(&WRITER).take() {
+        let _ = handle.join();
+    }
+}
+
+/// Returns `true` if asynchronous mode is enabled.
+pub fn async_enabled() -> bool {
+    lock(&QUEUE).active
+}
+
+/// Blocks until every queued record has been written, then flushes all the sinks.
+pub fn flush_async() {
+    if IS_WRITER.with(Cell::get) {
+        flush_sinks();
+        return;
+    }
+    let mut queue = lock(&QUEUE);
+    while queue.active && (!queue.records.is_empty() || queue.in_flight > 0) {
+        queue = IDLE.wait(queue).unwrap_or_else(PoisonError::into_inner);
+    }
+    drop(queue);
+    flush_sinks();
+}
+
+/// Returns the number of records dropped because the queue was full.
+pub fn dropped_records() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Pushes the record into the queue. Gives the record back if asynchronous mode is disabled
+/// (or if called from the writer thread), so that the caller writes it synchronously.
+pub(crate) fn submit(record: Record) -> Option<Record> {
+    if IS_WRITER.with(Cell::get) {
+        return Some(record);
+    }
+    let mut queue = lock(&QUEUE);
+    if !queue.active {
+        return Some(record);
+    }
+    if queue.records.len() >= queue.config.capacity {
+        match queue.config.overflow {
+            OverflowPolicy::Block => {
+                while queue.active && queue.records.len() >= queue.config.capacity {
+                    queue = NOT_FULL.wait(queue).unwrap_or_else(PoisonError::into_inner);
+                }
+                if !queue.active {
+                    return Some(record);
+                }
+            }
+            OverflowPolicy::DropNewest => {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            OverflowPolicy::DropOldest => {
+                queue.records.pop_front();
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+    queue.records.push_back(record);
+    NOT_EMPTY.notify_one();
+    None
+}
+
+fn run_writer() {
+    IS_WRITER.with(|is_writer| is_writer.set(true));
+    loop {
+        let mut queue = lock(&QUEUE);
+        while queue.records.is_empty() && queue.active {
+            queue = NOT_EMPTY.wait(queue).unwrap_or_else(PoisonError::into_inner);
+        }
+        if queue.records.is_empty() {
+            break;
+        }
+        let batch: Vec<Record> = queue.records.drain(..).collect();
+        queue.in_flight = batch.len();
+        drop(queue);
+        NOT_FULL.notify_all();
+
+        for record in &batch {
+            dispatch(record);
+        }
+
+        lock(&QUEUE).in_flight = 0;
+        IDLE.notify_all();
+    }
+    IDLE.notify_all();
+    flush_sinks();
+}
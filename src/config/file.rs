@@ -0,0 +1,463 @@
+//! # Configuration files
+//! `load_config` reads the whole logger setup from a TOML file. A simple INI-like syntax works too:
+//! quotes around values are optional, and comments can start with `#` or `;`.
+//! ```toml
+//! [logger]
+//! output = "both"                  # console | file | both
+//! level = "my_app::db=warn,info"   # a severity, or per-module directives (see `Filter`)
+//! path = "/var/log/my_app"
+//! file_name = "app.log"            # or "per-process"
+//! console_format = "colored"       # colored | plain | json | logfmt
+//! file_format = "json"
+//!
+//! [rotation]
+//! max_size = "10M"                 # bytes, or with a K, M or G suffix
+//! max_time = "1d"                  # seconds, or with a s, m, h or d suffix
+//! max_files = 7
+//! max_age = "30d"
+//! max_total_size = "1G"
+//! compression = "gzip"             # none | gzip | zstd
+//! schedule = "daily 03:00"         # minutely | hourly | daily [HH:MM] | weekly <weekday> [HH:MM]
+//!
+//! [tracer]
+//! context_enabled = true
+//! timestamp_enabled = true
+//! timestamp_type = "chrono"        # unix | chrono
+//! file_enabled = true
+//! line_enabled = false
+//! format = "{expression} = {value}"
+//!
+//! [sinks]
+//! audit = true                     # sink types registered with `register_sink_type`
+//! ```
+//! Keys outside of any section belong to `[logger]`. Missing sections and keys leave the current configuration untouched,
+//! except for `[sinks]`, which replaces every sink registered with `add_sink` when present.
+//!
+//! The whole file is checked before anything is applied. Unknown sections or keys and invalid values
+//! fail with `LoggerError::ConfigFile`, which names the line:
+//! ```
+//! use logger_rust::*;
+//!
+//! let error = load_config_str("[logger]\noutput = \"both\"\ncolour = true").unwrap_err();
+//! assert_eq!(error.to_string(), "Invalid logger configuration at line 3: unknown key `colour` in section [logger]");
+//! ```
+use crate::config::{LogFileName, LogLevel, set_min_severity, LOG_LEVEL, LOG_ROTATOR_CONFIG};
+use crate::env::{current_log_dir, parse_duration, parse_level, parse_output, parse_size, LevelSetting};
+use crate::error::{lock, LoggerError};
+use crate::filter::try_set_filter;
+use crate::format::{Format, try_set_console_format, try_set_file_format};
+use crate::log_rotator::{Compression, LogConfig, LogPath, LogRotatorConfig, RotationSchedule, Weekday};
+use crate::set_log::{try_set_log_file_name, try_set_log_level, try_set_log_path, validate_log_dir, validate_log_file_name};
+use crate::sink::{new_sink, replace_sinks, Sink};
+use crate::tracer_config::{get_tracer_config, try_set_tracer_config, TimestampType, TracerConfiguration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Reads the configuration file at `path` and applies it.
+pub fn load_config<P: AsRef<Path>>(path: P) -> Result<(), LoggerError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| LoggerError::Io(path.to_path_buf(), e))?;
+    load_config_str(&text)
+}
+
+/// Same as `load_config`, for a configuration that is already in memory.
+///
+/// ```
+/// use logger_rust::*;
+///
+/// load_config_str(r#"
+///     output = console
+///     level = warn
+///
+///     [tracer]
+///     line_enabled = false
+/// "#).unwrap();
+/// assert_eq!(min_severity(), Severity::Warn);
+/// # set_min_severity(Severity::Trace);
+/// # set_tracer_config(TracerConfiguration::new());
+/// ```
+pub fn load_config_str(text: &str) -> Result<(), LoggerError> {
+    parse(text)?.apply()
+}
+
+/// A value as written in the file, with the line it was found on.
+struct Entry {
+    line: usize,
+    value: Setting,
+}
+
+enum Setting {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+}
+
+impl Entry {
+    fn error(&self, message: String) -> LoggerError {
+        LoggerError::ConfigFile(self.line, message)
+    }
+
+    fn str(&self) -> Result<&str, LoggerError> {
+        match &self.value {
+            Setting::Str(value) => Ok(value),
+            _ => Err(self.error("expected a string".to_string())),
+        }
+    }
+
+    fn bool(&self) -> Result<bool, LoggerError> {
+        match &self.value {
+            Setting::Bool(value) => Ok(*value),
+            _ => Err(self.error("expected `true` or `false`".to_string())),
+        }
+    }
+
+    fn count(&self) -> Result<usize, LoggerError> {
+        match &self.value {
+            Setting::Int(value) => usize::try_from(*value).map_err(|_| self.error(format!("{} is too large", value))),
+            _ => Err(self.error("expected a number".to_string())),
+        }
+    }
+
+    fn size(&self) -> Result<u64, LoggerError> {
+        match &self.value {
+            Setting::Int(value) => Ok(*value),
+            Setting::Str(value) => parse_size(value).map_err(|e| self.error(e)),
+            Setting::Bool(_) => Err(self.error("expected a size".to_string())),
+        }
+    }
+
+    fn duration(&self) -> Result<Duration, LoggerError> {
+        match &self.value {
+            Setting::Int(value) => Ok(Duration::from_secs(*value)),
+            Setting::Str(value) => parse_duration(value).map_err(|e| self.error(e)),
+            Setting::Bool(_) => Err(self.error("expected a duration".to_string())),
+        }
+    }
+
+    /// Attaches the line to the message of a `LoggerError::InvalidConfig`.
+    fn check(&self, result: Result<(), LoggerError>) -> Result<(), LoggerError> {
+        result.map_err(|e| match e {
+            LoggerError::InvalidConfig(message) => self.error(message),
+            e => self.error(e.to_string()),
+        })
+    }
+}
+
+/// Everything the file sets, checked but not applied yet.
+#[derive(Default)]
+struct Setup {
+    output: Option<LogLevel>,
+    level: Option<LevelSetting>,
+    path: Option<(usize, PathBuf)>,
+    file_name: Option<LogFileName>,
+    console_format: Option<Format>,
+    file_format: Option<Format>,
+    rotation: Option<(usize, Rotation)>,
+    tracer: Option<TracerConfiguration>,
+    sinks: Option<Vec<Box<dyn Sink>>>,
+}
+
+#[derive(Default)]
+struct Rotation {
+    max_size: Option<u64>,
+    max_time: Option<Duration>,
+    max_files: Option<usize>,
+    max_age: Option<Duration>,
+    max_total_size: Option<u64>,
+    compression: Option<Compression>,
+    schedule: Option<RotationSchedule>,
+}
+
+fn parse(text: &str) -> Result<Setup, LoggerError> {
+    let mut setup = Setup::default();
+    let mut section = "logger";
+    let mut seen: Vec<(String, String)> = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let name = strip_comment(header)
+                .strip_suffix(']')
+                .ok_or_else(|| LoggerError::ConfigFile(line, format!("missing `]` in section header `{}`", trimmed)))?
+                .trim();
+            section = match name {
+                "logger" | "rotation" | "tracer" | "sinks" => name,
+                _ => return Err(LoggerError::ConfigFile(line, format!("unknown section [{}]", name))),
+            };
+            match section {
+                "rotation" => {
+                    setup.rotation.get_or_insert_with(|| (line, Rotation::default()));
+                }
+                "tracer" => {
+                    setup.tracer.get_or_insert_with(get_tracer_config);
+                }
+                "sinks" => {
+                    setup.sinks.get_or_insert_with(Vec::new);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| LoggerError::ConfigFile(line, format!("expected `key = value`, found `{}`", trimmed)))?;
+        let key = key.trim().trim_matches('"');
+        if key.is_empty() {
+            return Err(LoggerError::ConfigFile(line, "missing key before `=`".to_string()));
+        }
+        if seen.iter().any(|(s, k)| s == section && k == key) {
+            return Err(LoggerError::ConfigFile(line, format!("duplicate key `{}` in section [{}]", key, section)));
+        }
+        seen.push((section.to_string(), key.to_string()));
+        let entry = Entry { line, value: parse_value(value.trim()).map_err(|e| LoggerError::ConfigFile(line, e))? };
+        setup.set(section, key, &entry)?;
+    }
+    setup.check()?;
+    Ok(setup)
+}
+
+/// Removes a trailing `# comment` or `; comment`.
+fn strip_comment(text: &str) -> &str {
+    text.find(['#', ';']).map_or(text, |index| &text[..index]).trim()
+}
+
+fn parse_value(text: &str) -> Result<Setting, String> {
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    return match strip_comment(&rest[index + 1..]) {
+                        "" => Ok(Setting::Str(value)),
+                        trailing => Err(format!("unexpected `{}` after the string", trailing)),
+                    };
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some(c) => return Err(format!("unknown escape sequence `\\{}`", c)),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        return Err("missing closing `\"`".to_string());
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let (value, trailing) = rest.split_once('\'').ok_or_else(|| "missing closing `'`".to_string())?;
+        return match strip_comment(trailing) {
+            "" => Ok(Setting::Str(value.to_string())),
+            trailing => Err(format!("unexpected `{}` after the string", trailing)),
+        };
+    }
+    let value = strip_comment(text);
+    match value {
+        "" => Err("missing value".to_string()),
+        "true" => Ok(Setting::Bool(true)),
+        "false" => Ok(Setting::Bool(false)),
+        _ if value.chars().all(|c| c.is_ascii_digit() || c == '_') && value.starts_with(|c: char| c.is_ascii_digit()) => value
+            .replace('_', "")
+            .parse()
+            .map(Setting::Int)
+            .map_err(|_| format!("{} is too large", value)),
+        _ => Ok(Setting::Str(value.to_string())),
+    }
+}
+
+fn parse_format(entry: &Entry) -> Result<Format, LoggerError> {
+    match entry.str()?.to_ascii_lowercase().as_str() {
+        "colored" | "coloured" => Ok(Format::Colored),
+        "plain" => Ok(Format::Plain),
+        "json" => Ok(Format::Json),
+        "logfmt" => Ok(Format::Logfmt),
+        other => Err(entry.error(format!("`{}` is not a format (expected `colored`, `plain`, `json` or `logfmt`)", other))),
+    }
+}
+
+fn parse_compression(entry: &Entry) -> Result<Compression, LoggerError> {
+    let compression = match entry.str()?.to_ascii_lowercase().as_str() {
+        "none" => Compression::None,
+        "gzip" => Compression::Gzip,
+        "zstd" => Compression::Zstd,
+        other => return Err(entry.error(format!("`{}` is not a compression (expected `none`, `gzip` or `zstd`)", other))),
+    };
+    match compression.missing_feature() {
+        Some(feature) => Err(entry.error(format!("`{}` compression requires the `{}` cargo feature", feature, feature))),
+        None => Ok(compression),
+    }
+}
+
+fn parse_schedule(entry: &Entry) -> Result<RotationSchedule, LoggerError> {
+    let value = entry.str()?;
+    let error = || {
+        entry.error(format!(
+            "`{}` is not a schedule (expected `minutely`, `hourly`, `daily [HH:MM]` or `weekly <weekday> [HH:MM]`)",
+            value
+        ))
+    };
+    let time = |word: Option<&str>| -> Result<(u32, u32), LoggerError> {
+        let Some(word) = word else {
+            return Ok((0, 0));
+        };
+        let (hour, minute) = word.split_once(':').ok_or_else(error)?;
+        match (hour.parse(), minute.parse()) {
+            (Ok(hour), Ok(minute)) if hour < 24 && minute < 60 => Ok((hour, minute)),
+            _ => Err(error()),
+        }
+    };
+    let mut words = value.split_whitespace();
+    let schedule = match words.next().map(str::to_ascii_lowercase).as_deref() {
+        Some("minutely") => RotationSchedule::Minutely,
+        Some("hourly") => RotationSchedule::Hourly,
+        Some("daily") => {
+            let (hour, minute) = time(words.next())?;
+            RotationSchedule::Daily { hour, minute }
+        }
+        Some("weekly") => {
+            let weekday: Weekday = words.next().ok_or_else(error)?.parse().map_err(|_| error())?;
+            let (hour, minute) = time(words.next())?;
+            RotationSchedule::Weekly { weekday, hour, minute }
+        }
+        _ => return Err(error()),
+    };
+    match words.next() {
+        Some(_) => Err(error()),
+        None => Ok(schedule),
+    }
+}
+
+impl Setup {
+    fn set(&mut self, section: &str, key: &str, entry: &Entry) -> Result<(), LoggerError> {
+        let unknown = || entry.error(format!("unknown key `{}` in section [{}]", key, section));
+        match section {
+            "logger" => match key {
+                "output" => self.output = Some(parse_output(entry.str()?).map_err(|e| entry.error(e))?),
+                "level" => self.level = Some(parse_level(entry.str()?).map_err(|e| entry.error(e))?),
+                "path" => {
+                    let path = PathBuf::from(entry.str()?);
+                    entry.check(validate_log_dir(&path))?;
+                    self.path = Some((entry.line, path));
+                }
+                "file_name" => {
+                    let file_name = match entry.str()? {
+                        "per-process" => LogFileName::PerProcess,
+                        name => LogFileName::Fixed(name.to_string()),
+                    };
+                    entry.check(validate_log_file_name(&file_name))?;
+                    self.file_name = Some(file_name);
+                }
+                "console_format" => self.console_format = Some(parse_format(entry)?),
+                "file_format" => self.file_format = Some(parse_format(entry)?),
+                _ => return Err(unknown()),
+            },
+            "rotation" => {
+                let rotation = &mut self.rotation.get_or_insert_with(|| (entry.line, Rotation::default())).1;
+                match key {
+                    "max_size" => rotation.max_size = Some(entry.size()?),
+                    "max_time" => rotation.max_time = Some(entry.duration()?),
+                    "max_files" => rotation.max_files = Some(entry.count()?),
+                    "max_age" => rotation.max_age = Some(entry.duration()?),
+                    "max_total_size" => rotation.max_total_size = Some(entry.size()?),
+                    "compression" => rotation.compression = Some(parse_compression(entry)?),
+                    "schedule" => rotation.schedule = Some(parse_schedule(entry)?),
+                    _ => return Err(unknown()),
+                }
+            }
+            "tracer" => {
+                let tracer = self.tracer.get_or_insert_with(get_tracer_config);
+                match key {
+                    "context_enabled" => tracer.context_enabled = Some(entry.bool()?),
+                    "timestamp_enabled" => tracer.timestamp_enabled = Some(entry.bool()?),
+                    "file_enabled" => tracer.file_enabled = Some(entry.bool()?),
+                    "line_enabled" => tracer.line_enabled = Some(entry.bool()?),
+                    "format" => tracer.format = Some(entry.str()?.to_string()),
+                    "timestamp_type" => {
+                        tracer.timestamp_type = Some(match entry.str()?.to_ascii_lowercase().as_str() {
+                            "unix" => TimestampType::Unix,
+                            "chrono" => TimestampType::Chrono,
+                            other => return Err(entry.error(format!("`{}` is not a timestamp type (expected `unix` or `chrono`)", other))),
+                        })
+                    }
+                    _ => return Err(unknown()),
+                }
+            }
+            _ => {
+                let enabled = entry.bool()?;
+                let sink = new_sink(key).ok_or_else(|| {
+                    entry.error(format!("unknown sink type `{}` (register it with `register_sink_type`)", key))
+                })?;
+                if enabled {
+                    self.sinks.get_or_insert_with(Vec::new).push(sink);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the settings that depend on each other.
+    fn check(&self) -> Result<(), LoggerError> {
+        let output = self.output.unwrap_or_else(|| *lock(&LOG_LEVEL));
+        let line = self.path.as_ref().map(|(line, _)| *line).or(self.rotation.as_ref().map(|(line, _)| *line));
+        match line {
+            Some(line) if output == LogLevel::Console => Err(LoggerError::ConfigFile(
+                line,
+                "the log path and rotation can only be set when the output is `file` or `both`".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(self) -> Result<(), LoggerError> {
+        if let Some(output) = self.output {
+            try_set_log_level(output)?;
+        }
+        match self.level {
+            Some(LevelSetting::Min(severity)) => set_min_severity(severity),
+            Some(LevelSetting::Filter(filter)) => try_set_filter(filter)?,
+            None => {}
+        }
+        if let Some(format) = self.console_format {
+            try_set_console_format(format)?;
+        }
+        if let Some(format) = self.file_format {
+            try_set_file_format(format)?;
+        }
+        if let Some(file_name) = self.file_name {
+            try_set_log_file_name(file_name)?;
+        }
+        let path = self.path.map(|(_, path)| path);
+        if let Some((_, rotation)) = self.rotation {
+            let log_path = path.unwrap_or_else(current_log_dir);
+            let mut config = lock(&LOG_ROTATOR_CONFIG)
+                .clone()
+                .unwrap_or_else(|| LogRotatorConfig::new(log_path.clone(), u64::MAX, Duration::MAX));
+            config.log_path = log_path;
+            config.max_size = rotation.max_size.unwrap_or(config.max_size);
+            config.max_time = rotation.max_time.unwrap_or(config.max_time);
+            config.max_files = rotation.max_files.or(config.max_files);
+            config.max_age = rotation.max_age.or(config.max_age);
+            config.max_total_size = rotation.max_total_size.or(config.max_total_size);
+            config.compression = rotation.compression.unwrap_or(config.compression);
+            config.schedule = rotation.schedule.or(config.schedule);
+            try_set_log_path(LogConfig::Rotator(config))?;
+        } else if let Some(path) = path {
+            try_set_log_path(LogConfig::Path(LogPath::Path(path)))?;
+        }
+        if let Some(tracer) = self.tracer {
+            try_set_tracer_config(tracer)?;
+        }
+        if let Some(sinks) = self.sinks {
+            replace_sinks(sinks)?;
+        }
+        Ok(())
+    }
+}
@@ -1,15 +1,28 @@
 pub use crate::log_rotator::LogRotatorConfig;
 
+pub mod file;
+
 use std::{
+    fmt,
     sync::Mutex,
-    path::PathBuf
+    sync::atomic::{AtomicU8, Ordering},
+    path::PathBuf,
+    time::Duration,
 };
 
+/// The current severity threshold, stored as the rank of a `Severity`.
+/// Kept outside of a `Mutex` so that the macros can check it before formatting anything.
+static MIN_SEVERITY: AtomicU8 = AtomicU8::new(Severity::Trace as u8);
+
 lazy_static::lazy_static! {
     pub static ref LOG_LEVEL: Mutex<LogLevel> = Mutex::new(LogLevel::Console);
     pub static ref LOG_PATH: Mutex<PathBuf> = Mutex::new(PathBuf::new());
     pub static ref LOG_MUTEX: Mutex<()> = Mutex::new(());
     pub static ref LOG_ROTATOR_CONFIG: Mutex<Option<LogRotatorConfig>> = Mutex::new(None);
+    pub static ref FLUSH_POLICY: Mutex<FlushPolicy> = Mutex::new(FlushPolicy::EveryRecord);
+    pub static ref LOG_FILE_NAME: Mutex<LogFileName> = Mutex::new(LogFileName::PerProcess);
+    /// The time the logger was first used, used by `LogFileName::PerProcess`.
+    pub static ref PROCESS_START: String = chrono::Local::now().format("%Y-%m-%d@%H-%M-%S").to_string();
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -24,6 +37,46 @@ pub enum LogLevel {
     Both
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogFileName {
+/// # LogFileName enum
+/// Defines the name of the active log file inside the log directory:
+/// - `PerProcess` names it after the time the process started logging (e.g. `2023-06-04@12-30-00.log`), the default;
+/// - `Fixed(name)` always uses the same name (e.g. `app.log`), so the file keeps growing across restarts.
+///
+/// The active file is written continuously until the `LogRotatorConfig` thresholds rotate it
+/// to `<name>_rot-N.log`, after which a fresh active file with the same name is started.
+    PerProcess,
+    Fixed(String),
+}
+
+impl LogFileName {
+    /// Returns the file name of the active log file.
+    pub fn file_name(&self) -> String {
+        match self {
+            LogFileName::PerProcess => format!("{}.log", *PROCESS_START),
+            LogFileName::Fixed(name) => name.clone(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+/// # FlushPolicy enum
+/// Defines when the buffered log file is flushed to disk:
+/// - `EveryRecord` flushes after every line (the default);
+/// - `Interval(duration)` flushes at most once per `duration`, a background thread takes care of idle periods;
+/// - `MinSeverity(severity)` flushes after lines that are at least as important as `severity` (e.g. `Severity::Warn`),
+///   and after lines of unknown importance (written with `log_to_file` or `append_to_file`, or with an unknown level).
+///
+/// Whatever the policy, `flush_sinks` flushes the file immediately. The buffered lines are **not** flushed
+/// when the process exits, so call `flush_sinks` before returning from `main` (or calling `std::process::exit`)
+/// with `Interval` or `MinSeverity`, or the tail of the log is lost.
+    EveryRecord,
+    Interval(Duration),
+    MinSeverity(Severity),
+}
+
 pub trait LogVariables {
 /// Trait that defines a method for accessing the current log level.
 ///
@@ -48,3 +101,92 @@ impl LogVariables for LogVariablesImpl {
         &LOG_LEVEL
     }
 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+/// # Severity enum
+/// Defines the severity of a log record: `Error`, `Warn`, `Info`, `Debug`, `Trace`.
+/// Unlike `LogLevel`, which decides **where** records go, the severity decides **whether** they are emitted at all.
+/// Variants are ordered from the most to the least important one, so `Severity::Error < Severity::Trace`.
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Severity {
+    /// Returns the level string used in the log output (e.g. `"ERROR"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "ERROR",
+            Severity::Warn => "WARN",
+            Severity::Info => "INFO",
+            Severity::Debug => "DEBUG",
+            Severity::Trace => "TRACE",
+        }
+    }
+
+    /// Parses a level string such as `"WARN"` or `"debug"` into a `Severity`.
+    /// Returns `None` for levels that are not known to the crate.
+    pub fn from_level(level: &str) -> Option<Severity> {
+        match level.to_ascii_uppercase().as_str() {
+            "ERROR" => Some(Severity::Error),
+            "WARN" | "WARNING" => Some(Severity::Warn),
+            "INFO" => Some(Severity::Info),
+            "DEBUG" => Some(Severity::Debug),
+            "TRACE" => Some(Severity::Trace),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_rank(rank: u8) -> Severity {
+        match rank {
+            1 => Severity::Error,
+            2 => Severity::Warn,
+            3 => Severity::Info,
+            4 => Severity::Debug,
+            _ => Severity::Trace,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Sets the minimum severity that will be emitted.
+/// Records that are less important than `severity` are dropped before their message is even formatted.
+///
+/// # Examples
+///
+/// ```
+/// use logger_rust::*;
+///
+/// set_min_severity(Severity::Info);
+/// log_debug!("This line is never formatted: {}", 42);
+/// log_info!("But this one is");
+/// # set_min_severity(Severity::Trace);
+/// ```
+pub fn set_min_severity(severity: Severity) {
+    MIN_SEVERITY.store(severity as u8, Ordering::Relaxed);
+}
+
+/// Returns the current minimum severity (defaults to `Severity::Trace`, i.e. everything is emitted).
+pub fn min_severity() -> Severity {
+    Severity::from_rank(MIN_SEVERITY.load(Ordering::Relaxed))
+}
+
+/// Returns `true` if records of the given severity pass the current threshold.
+#[inline]
+pub fn severity_enabled(severity: Severity) -> bool {
+    rank_enabled(severity as u8)
+}
+
+/// Same as `severity_enabled`, for the rank of a built-in or custom level (see `register_level`).
+#[inline]
+pub(crate) fn rank_enabled(rank: u8) -> bool {
+    rank <= MIN_SEVERITY.load(Ordering::Relaxed)
+}
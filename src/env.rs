@@ -0,0 +1,191 @@
+//! # Environment configuration
+//! `init_from_env` configures the logger from environment variables, which is handy in containers:
+//! - `LOGGER_RUST_OUTPUT`: where records go, `console`, `file` or `both` (see `LogLevel`);
+//! - `LOGGER_RUST_LEVEL`: the minimum severity (e.g. `info`), or per-module directives such as `db=warn,info` (see `Filter`);
+//! - `LOGGER_RUST_PATH`: the log directory (see `set_log_path`);
+//! - `LOGGER_RUST_MAX_SIZE`: the size after which the log file is rotated, in bytes or with a `K`, `M` or `G` suffix (e.g. `10M`);
+//! - `LOGGER_RUST_MAX_AGE`: the lifetime after which the log file is rotated, in seconds or with a `s`, `m`, `h` or `d` suffix (e.g. `1d`).
+//!
+//! Unset (or empty) variables leave the current configuration untouched. `LOGGER_RUST_MAX_SIZE` and `LOGGER_RUST_MAX_AGE`
+//! only replace those two thresholds of the current rotator, its retention, compression and schedule are kept.
+//! ```
+//! use logger_rust::*;
+//!
+//! let dir = std::env::temp_dir();
+//! std::env::set_var("LOGGER_RUST_OUTPUT", "both");
+//! std::env::set_var("LOGGER_RUST_LEVEL", "info");
+//! std::env::set_var("LOGGER_RUST_PATH", &dir);
+//! std::env::set_var("LOGGER_RUST_MAX_SIZE", "10M");
+//! init_from_env().unwrap();
+//! assert_eq!(min_severity(), Severity::Info);
+//!
+//! std::env::set_var("LOGGER_RUST_MAX_AGE", "a week");
+//! assert_eq!(
+//!     init_from_env().unwrap_err().to_string(),
+//!     "Invalid logger configuration: LOGGER_RUST_MAX_AGE: `a week` is not a duration (expected e.g. `90`, `30s`, `15m`, `12h` or `7d`)"
+//! );
+//!
+//! // Nothing is applied when a variable is invalid
+//! std::env::set_var("LOGGER_RUST_MAX_AGE", "1d");
+//! std::env::set_var("LOGGER_RUST_OUTPUT", "file");
+//! std::env::set_var("LOGGER_RUST_PATH", "/definitely/not/here");
+//! assert!(matches!(init_from_env(), Err(LoggerError::PathNotFound(_))));
+//! assert!(*LogVariablesImpl.log_level().lock().unwrap() == LogLevel::Both);
+//! # set_log_level(LogLevel::Console);
+//! ```
+use crate::config::{LogLevel, Severity, set_min_severity, LOG_LEVEL, LOG_PATH, LOG_ROTATOR_CONFIG};
+use crate::error::{lock, LoggerError};
+use crate::filter::{Filter, try_set_filter};
+use crate::log_rotator::{LogConfig, LogPath, LogRotatorConfig};
+use crate::set_log::{try_set_log_level, try_set_log_path, validate_log_dir};
+use std::{
+    env,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Reads the `LOGGER_RUST_*` variables and applies them.
+/// Every variable is checked before anything is applied, so on error the configuration is left as it was.
+pub fn init_from_env() -> Result<(), LoggerError> {
+    let output = var("LOGGER_RUST_OUTPUT")?
+        .map(|value| parse_output(&value).map_err(|e| invalid("LOGGER_RUST_OUTPUT", e)))
+        .transpose()?;
+    let level = var("LOGGER_RUST_LEVEL")?
+        .map(|value| parse_level(&value).map_err(|e| invalid("LOGGER_RUST_LEVEL", e)))
+        .transpose()?;
+    let path = var("LOGGER_RUST_PATH")?.map(PathBuf::from);
+    let max_size = var("LOGGER_RUST_MAX_SIZE")?
+        .map(|value| parse_size(&value).map_err(|e| invalid("LOGGER_RUST_MAX_SIZE", e)))
+        .transpose()?;
+    let max_age = var("LOGGER_RUST_MAX_AGE")?
+        .map(|value| parse_duration(&value).map_err(|e| invalid("LOGGER_RUST_MAX_AGE", e)))
+        .transpose()?;
+    let rotation = max_size.is_some() || max_age.is_some();
+    let log_dir = match &path {
+        Some(path) => Some(path.clone()),
+        None if rotation => Some(current_log_dir()),
+        None => None,
+    };
+    if let Some(log_dir) = &log_dir {
+        if output.unwrap_or_else(|| *lock(&LOG_LEVEL)) == LogLevel::Console {
+            return Err(LoggerError::InvalidConfig(
+                "LOGGER_RUST_PATH, LOGGER_RUST_MAX_SIZE and LOGGER_RUST_MAX_AGE need LOGGER_RUST_OUTPUT to be `file` or `both`"
+                    .to_string(),
+            ));
+        }
+        validate_log_dir(log_dir)?;
+    }
+
+    if let Some(output) = output {
+        try_set_log_level(output)?;
+    }
+    match level {
+        Some(LevelSetting::Min(severity)) => set_min_severity(severity),
+        Some(LevelSetting::Filter(filter)) => try_set_filter(filter)?,
+        None => {}
+    }
+    match log_dir {
+        Some(log_path) if rotation => {
+            // Keep the retention, compression and schedule of the current rotator, if any
+            let mut config = lock(&LOG_ROTATOR_CONFIG)
+                .clone()
+                .unwrap_or_else(|| LogRotatorConfig::new(log_path.clone(), u64::MAX, Duration::MAX));
+            config.log_path = log_path;
+            config.max_size = max_size.unwrap_or(config.max_size);
+            config.max_time = max_age.unwrap_or(config.max_time);
+            try_set_log_path(LogConfig::Rotator(config))?;
+        }
+        Some(log_path) => try_set_log_path(LogConfig::Path(LogPath::Path(log_path)))?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// The value of `LOGGER_RUST_LEVEL`: a single severity, or per-module directives.
+pub(crate) enum LevelSetting {
+    Min(Severity),
+    Filter(Filter),
+}
+
+/// Reads the variable, treating an empty value as unset.
+fn var(name: &'static str) -> Result<Option<String>, LoggerError> {
+    match env::var(name) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value.trim().to_string())),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(invalid(name, "the value is not valid UTF-8".to_string())),
+    }
+}
+
+fn invalid(name: &str, message: String) -> LoggerError {
+    LoggerError::InvalidConfig(format!("{}: {}", name, message))
+}
+
+/// The directory the log file is currently written to, used when only the rotation thresholds are set.
+pub(crate) fn current_log_dir() -> PathBuf {
+    let log_path = lock(&LOG_PATH);
+    if log_path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        log_path.clone()
+    }
+}
+
+/// Parses `console`, `file` or `both` (case-insensitive).
+pub(crate) fn parse_output(value: &str) -> Result<LogLevel, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "console" => Ok(LogLevel::Console),
+        "file" => Ok(LogLevel::File),
+        "both" => Ok(LogLevel::Both),
+        _ => Err(format!("`{}` is not an output (expected `console`, `file` or `both`)", value)),
+    }
+}
+
+pub(crate) fn parse_level(value: &str) -> Result<LevelSetting, String> {
+    if let Some(severity) = Severity::from_level(value) {
+        return Ok(LevelSetting::Min(severity));
+    }
+    Filter::parse(value).map(LevelSetting::Filter).map_err(|e| match e {
+        LoggerError::InvalidConfig(message) => message,
+        e => e.to_string(),
+    })
+}
+
+/// Parses a size in bytes, with an optional `K`, `M` or `G` suffix (powers of 1024, `KB`, `KiB`, ... are accepted too).
+pub(crate) fn parse_size(value: &str) -> Result<u64, String> {
+    let error = || format!("`{}` is not a size (expected e.g. `1048576`, `512K`, `10M` or `1G`)", value);
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match value[digits.len()..].to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(error()),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|size| size.checked_mul(multiplier))
+        .ok_or_else(error)
+}
+
+/// Parses a duration in seconds, with an optional `s`, `m`, `h` or `d` suffix.
+pub(crate) fn parse_duration(value: &str) -> Result<Duration, String> {
+    let error = || format!("`{}` is not a duration (expected e.g. `90`, `30s`, `15m`, `12h` or `7d`)", value);
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match &value[digits.len()..] {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(error()),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|seconds| seconds.checked_mul(multiplier))
+        .map(Duration::from_secs)
+        .ok_or_else(error)
+}
@@ -0,0 +1,177 @@
+//! # LoggerError
+//! The error type of the crate.
+//! - Every configuration function has a fallible `try_*` variant returning `Result<(), LoggerError>`;
+//! - Errors that happen while writing records (I/O errors of the sinks, failed rotations, ...) cannot be returned
+//!   to the caller of `log_info!` and friends, so they are passed to the error handler instead
+//!   (see `set_error_handler`), which prints them to stderr by default.
+//!
+//! The infallible configuration functions recover from poisoned locks (a thread panicked while holding them),
+//! while their `try_*` variants report it as `LoggerError::Poisoned`.
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt,
+    io,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// A function receiving the errors that happen while records are written.
+type ErrorHandler = Box<dyn Fn(&LoggerError) + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref ERROR_HANDLER: RwLock<Option<ErrorHandler>> = RwLock::new(None);
+}
+
+thread_local! {
+    /// Set while the error handler runs, so that errors raised by the handler itself
+    /// (e.g. because it logs through the failing sink) are printed instead of recursing.
+    static IN_HANDLER: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LoggerError {
+    /// The log directory does not exist.
+    PathNotFound(PathBuf),
+    /// The log path exists, but is not a directory.
+    NotADirectory(PathBuf),
+    /// The log directory is read-only.
+    PermissionDenied(PathBuf),
+    /// The configuration is not valid (e.g. `set_log_path` is called while logging to the console only).
+    InvalidConfig(String),
+    /// Any other I/O error, with the path it happened on.
+    Io(PathBuf, io::Error),
+    /// A thread panicked while holding the lock of the named logger state.
+    Poisoned(&'static str),
+    /// Rotating, compressing or cleaning up the log file at the given path failed.
+    /// The logger keeps writing to the current file.
+    Rotation(PathBuf, io::Error),
+    /// A sink failed; the message describes what was being done (e.g. "write to the console").
+    Sink(String, io::Error),
+    /// A background thread of the logger (writer, flusher) could not be started.
+    Spawn(io::Error),
+    /// A configuration file (see `load_config`) is invalid; the number is the 1-based line of the problem.
+    ConfigFile(usize, String),
+}
+
+impl fmt::Display for LoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggerError::PathNotFound(path) => write!(f, "Path is not correct: {}", path.display()),
+            LoggerError::NotADirectory(path) => write!(f, "Path is not a directory: {}", path.display()),
+            LoggerError::PermissionDenied(path) => write!(f, "Not enough permissions to access the path: '{}'", path.display()),
+            LoggerError::InvalidConfig(message) => write!(f, "Invalid logger configuration: {}", message),
+            LoggerError::Io(path, e) => write!(f, "I/O error on path {}: {}", path.display(), e),
+            LoggerError::Poisoned(name) => write!(f, "The logger state `{}` is poisoned by a panicked thread", name),
+            LoggerError::Rotation(path, e) => write!(f, "Failed to rotate log file {}: {}", path.display(), e),
+            LoggerError::Sink(action, e) => write!(f, "Failed to {}: {}", action, e),
+            LoggerError::Spawn(e) => write!(f, "Failed to spawn a logger thread: {}", e),
+            LoggerError::ConfigFile(line, message) => write!(f, "Invalid logger configuration at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for LoggerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoggerError::Io(_, e) | LoggerError::Rotation(_, e) | LoggerError::Sink(_, e) | LoggerError::Spawn(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the function that receives the errors happening while records are written.
+/// By default, they are printed to stderr.
+///
+/// ```
+/// use logger_rust::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static ROTATION_FAILURES: AtomicUsize = AtomicUsize::new(0);
+///
+/// set_error_handler(|e| {
+///     if let LoggerError::Rotation(..) = e {
+///         ROTATION_FAILURES.fetch_add(1, Ordering::Relaxed);
+///     }
+///     eprintln!("logger: {}", e);
+/// });
+/// # reset_error_handler();
+/// ```
+///
+/// The handler may log itself, e.g. to keep the errors in the log file:
+/// ```
+/// use logger_rust::*;
+/// use std::{fs, sync::mpsc, thread, time::Duration};
+///
+/// let dir = std::env::temp_dir().join(format!("logger-rust-handler-{}", std::process::id()));
+/// fs::create_dir_all(&dir).unwrap();
+/// set_log_level(LogLevel::File);
+/// set_log_file_name(LogFileName::Fixed("app.log".to_string()));
+/// set_log_path(LogConfig::Rotator(LogRotatorConfig::new(dir.clone(), 0, Duration::MAX)));
+/// set_error_handler(|e| log_error!("logger: {}", e));
+///
+/// let (done, finished) = mpsc::channel();
+/// let active = dir.join("app.log");
+/// thread::spawn(move || {
+///     log_info!("First record");
+///     fs::remove_file(&active).unwrap(); // the next rotation fails
+///     log_info!("Second record");
+///     done.send(()).unwrap();
+/// });
+/// finished.recv_timeout(Duration::from_secs(10)).expect("the error handler deadlocked the logger");
+/// let logged = fs::read_dir(&dir).unwrap()
+///     .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+///     .collect::<String>();
+/// assert!(logged.contains("logger: Failed to rotate log file"));
+/// # reset_error_handler();
+/// # set_log_level(LogLevel::Console);
+/// # fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn set_error_handler<F: Fn(&LoggerError) + Send + Sync + 'static>(handler: F) {
+    *write(&ERROR_HANDLER) = Some(Box::new(handler));
+}
+
+/// Restores the default error handler, which prints errors to stderr.
+pub fn reset_error_handler() {
+    *write(&ERROR_HANDLER) = None;
+}
+
+/// Passes the error to the error handler.
+pub(crate) fn report_error(error: LoggerError) {
+    if IN_HANDLER.with(|in_handler| in_handler.replace(true)) {
+        eprintln!("{}", error);
+        return;
+    }
+    match read(&ERROR_HANDLER).as_ref() {
+        Some(handler) => handler(&error),
+        None => eprintln!("{}", error),
+    }
+    IN_HANDLER.with(|in_handler| in_handler.set(false));
+}
+
+/// Locks the mutex, recovering the data if a thread panicked while holding it:
+/// logging must keep working after a panic anywhere else in the program.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks the mutex, reporting poisoning as `LoggerError::Poisoned(name)`.
+pub(crate) fn try_lock<'a, T>(mutex: &'a Mutex<T>, name: &'static str) -> Result<MutexGuard<'a, T>, LoggerError> {
+    mutex.lock().map_err(|_| LoggerError::Poisoned(name))
+}
+
+/// Same as `lock`, for reading a `RwLock`.
+pub(crate) fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Same as `lock`, for writing a `RwLock`.
+pub(crate) fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Same as `try_lock`, for writing a `RwLock`.
+pub(crate) fn try_write<'a, T>(lock: &'a RwLock<T>, name: &'static str) -> Result<RwLockWriteGuard<'a, T>, LoggerError> {
+    lock.write().map_err(|_| LoggerError::Poisoned(name))
+}
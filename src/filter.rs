@@ -0,0 +1,183 @@
+//! # Filters
+//! Per-module severity filters, configured with `RUST_LOG`-style directives such as `db=warn,http::client=debug,info`:
+//! - `module=level` sets the level of the module and of its submodules (`db` matches `db` and `db::pool`, but not `dbx`);
+//! - a bare `level` sets the level of every module without a directive of its own;
+//! - a bare `module` enables every level for that module;
+//! - `off` silences everything it applies to.
+//!
+//! When several directives match, the one with the longest module path wins. A record is matched by its target
+//! (e.g. the `target` of a `log` record) or, if it has none, by the module path of the macro call.
+//! Filters come on top of `set_min_severity`: a record has to pass both.
+//!
+//! ```
+//! use logger_rust::*;
+//!
+//! set_filter("my_app::db=warn,my_app::http::client=debug,info".parse().unwrap());
+//! assert!(module_enabled(Severity::Warn, "my_app::db::pool"));
+//! assert!(!module_enabled(Severity::Info, "my_app::db::pool"));
+//! assert!(module_enabled(Severity::Debug, "my_app::http::client"));
+//! assert!(!module_enabled(Severity::Debug, "my_app::http"));
+//! # clear_filter();
+//! ```
+use crate::config::{Severity, rank_enabled};
+use crate::level::{level_enabled, level_rank};
+use crate::error::{read, try_write, write, LoggerError};
+use crate::record::Record;
+use std::{
+    str::FromStr,
+    sync::RwLock,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether a filter is installed, so that the macros only take the lock when there is something to match.
+static FILTER_SET: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref FILTER: RwLock<Option<Filter>> = RwLock::new(None);
+}
+
+/// A set of per-module levels. A level of `None` means `off`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    default: Option<Severity>,
+    /// Sorted by decreasing length, so that the first match is the longest one.
+    modules: Vec<(String, Option<Severity>)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filter {
+    /// Creates a filter that lets everything through.
+    pub fn new() -> Self {
+        Self {
+            default: Some(Severity::Trace),
+            modules: Vec::new(),
+        }
+    }
+
+    /// Parses comma separated directives, see the module documentation.
+    /// Fails with `LoggerError::InvalidConfig` on an unknown level or an empty module name.
+    pub fn parse(directives: &str) -> Result<Self, LoggerError> {
+        let mut filter = Filter::new();
+        for directive in directives.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        return Err(LoggerError::InvalidConfig(format!("missing module name in directive `{}`", directive)));
+                    }
+                    let level = parse_level(level.trim()).ok_or_else(|| {
+                        LoggerError::InvalidConfig(format!("unknown level `{}` in directive `{}`", level.trim(), directive))
+                    })?;
+                    filter = filter.with_module(module, level);
+                }
+                None => match parse_level(directive) {
+                    Some(level) => filter = filter.with_default(level),
+                    None => filter = filter.with_module(directive, Some(Severity::Trace)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Sets the level of the modules that have no directive of their own (`None` for `off`).
+    pub fn with_default(mut self, level: Option<Severity>) -> Self {
+        self.default = level;
+        self
+    }
+
+    /// Sets the level of `module` and of its submodules (`None` for `off`).
+    /// A later directive for the same module replaces the previous one.
+    pub fn with_module(mut self, module: &str, level: Option<Severity>) -> Self {
+        self.modules.retain(|(prefix, _)| prefix != module);
+        let index = self.modules.partition_point(|(prefix, _)| prefix.len() >= module.len());
+        self.modules.insert(index, (module.to_string(), level));
+        self
+    }
+
+    /// Returns `true` if a record of the given severity from the given module (or target) passes the filter.
+    /// Records without a module are checked against the default level.
+    pub fn enabled(&self, severity: Severity, module: Option<&str>) -> bool {
+        self.enabled_rank(severity as u8, module)
+    }
+
+    /// Same as `enabled`, for the rank of a built-in or custom level (see `register_level`).
+    fn enabled_rank(&self, rank: u8, module: Option<&str>) -> bool {
+        let level = module
+            .and_then(|module| self.modules.iter().find(|(prefix, _)| is_prefix(prefix, module)))
+            .map_or(self.default, |(_, level)| *level);
+        level.is_some_and(|level| rank <= level as u8)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = LoggerError;
+
+    fn from_str(directives: &str) -> Result<Self, Self::Err> {
+        Filter::parse(directives)
+    }
+}
+
+/// Parses a directive level: a `Severity` name, or `off`.
+fn parse_level(level: &str) -> Option<Option<Severity>> {
+    if level.eq_ignore_ascii_case("off") {
+        Some(None)
+    } else {
+        Severity::from_level(level).map(Some)
+    }
+}
+
+/// Returns `true` if `module` is `prefix` itself or one of its submodules.
+fn is_prefix(prefix: &str, module: &str) -> bool {
+    module
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Installs the filter, replacing the previous one.
+pub fn set_filter(filter: Filter) {
+    *write(&FILTER) = Some(filter);
+    FILTER_SET.store(true, Ordering::Relaxed);
+}
+
+/// Same as `set_filter`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_filter(filter: Filter) -> Result<(), LoggerError> {
+    *try_write(&FILTER, "filter")? = Some(filter);
+    FILTER_SET.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Removes the filter, so that only `set_min_severity` applies.
+pub fn clear_filter() {
+    FILTER_SET.store(false, Ordering::Relaxed);
+    *write(&FILTER) = None;
+}
+
+/// Returns `true` if records of the given severity from the given module pass both the severity threshold and the filter.
+/// Used by the logging macros before formatting anything.
+#[inline]
+pub fn module_enabled(severity: Severity, module: &str) -> bool {
+    level_enabled(severity.into(), module)
+}
+
+/// Checks a record that is about to be written. Records with a level unknown to the crate always pass.
+pub(crate) fn record_enabled(record: &Record) -> bool {
+    match level_rank(&record.level) {
+        Some(rank) => {
+            rank_enabled(rank) && filter_enabled(rank, record.target.as_deref().or(record.module_path.as_deref()))
+        }
+        None => true,
+    }
+}
+
+/// Checks the rank of a record from the given module against the installed filter, if any.
+pub(crate) fn filter_enabled(rank: u8, module: Option<&str>) -> bool {
+    if !FILTER_SET.load(Ordering::Relaxed) {
+        return true;
+    }
+    read(&FILTER).as_ref().is_none_or(|filter| filter.enabled_rank(rank, module))
+}
@@ -0,0 +1,284 @@
+//! # Formats
+//! Defines how a `Record` is rendered into a single output line.
+//! The console and the log file use separate formats, so you can keep the coloured output in your terminal
+//! while writing plain or machine readable lines into files:
+//!
+//! ```
+//! use logger_rust::*;
+//!
+//! set_console_format(Format::Colored);
+//! set_file_format(Format::Json);
+//!
+//! let mut record = Record::new("INFO", "2023-06-09 15:06:46", "order placed");
+//! record.fields.push(("user_id".to_string(), Value::from(42)));
+//! assert!(Format::Json.render(&record).starts_with(
+//!     r#"{"timestamp":"2023-06-09 15:06:46","level":"INFO","message":"order placed","#
+//! ));
+//! # set_file_format(Format::Plain);
+//!
+//! assert_eq!(
+//!     Format::Logfmt.render(&Record::new("WARN", "2023-06-09T15:06:46", "disk \"sda\" is full")),
+//!     r#"ts=2023-06-09T15:06:46 level=warn msg="disk \"sda\" is full""#
+//! );
+//! ```
+use crate::{
+    error::{read, try_write, write, LoggerError},
+    theme::theme,
+    record::{Record, Value},
+};
+use std::{
+    fmt::Write,
+    sync::RwLock,
+};
+
+lazy_static::lazy_static! {
+    static ref CONSOLE_FORMAT: RwLock<Format> = RwLock::new(Format::Colored);
+    static ref FILE_FORMAT: RwLock<Format> = RwLock::new(Format::Plain);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+/// # Format enum
+/// - The `Colored` format is the classic `now [LEVEL] message` line with ANSI colours;
+/// - The `Plain` format is the same line without any escape sequences (the default for log files);
+/// - The `Json` format produces one JSON object per line (JSON Lines) with `timestamp`, `level`, `message`,
+///   `module`, `file`, `line`, `thread` and, if there are any, structured `fields`;
+/// - The `Logfmt` format produces `ts=... level=info msg="..." key=value` lines.
+    Colored,
+    Plain,
+    Json,
+    Logfmt,
+}
+
+impl Format {
+    /// Renders the record as a single line (without the trailing newline).
+    pub fn render(&self, record: &Record) -> String {
+        match self {
+            Format::Colored => render_text(record, true),
+            Format::Plain => render_text(record, false),
+            Format::Json => render_json(record),
+            Format::Logfmt => render_logfmt(record),
+        }
+    }
+}
+
+/// Sets the format used by the console output.
+pub fn set_console_format(format: Format) {
+    *write(&CONSOLE_FORMAT) = format;
+}
+
+/// Same as `set_console_format`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_console_format(format: Format) -> Result<(), LoggerError> {
+    *try_write(&CONSOLE_FORMAT, "console format")? = format;
+    Ok(())
+}
+
+/// Sets the format used by the file output.
+/// Use `Format::Colored` to keep the colours in files (e.g. if you view them with `less -R`).
+pub fn set_file_format(format: Format) {
+    *write(&FILE_FORMAT) = format;
+}
+
+/// Same as `set_file_format`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_file_format(format: Format) -> Result<(), LoggerError> {
+    *try_write(&FILE_FORMAT, "file format")? = format;
+    Ok(())
+}
+
+/// Returns the format used by the console output (`Format::Colored` by default).
+pub fn console_format() -> Format {
+    *read(&CONSOLE_FORMAT)
+}
+
+/// Returns the format used by the file output (`Format::Plain` by default).
+pub fn file_format() -> Format {
+    *read(&FILE_FORMAT)
+}
+
+fn render_text(record: &Record, colored: bool) -> String {
+//! Renders the record as the `now [LEVEL] message` line, coloured or not.
+//! Records that carry a target (e.g. from the `log` bridge) also get a `target file:Lline - ` segment,
+//! and structured fields are appended as `key=value` suffixes.
+//! For the plain variant, escape sequences embedded in the message (e.g. by `log_trace!`) are stripped.
+    if !colored {
+        let location = match &record.target {
+            Some(target) => match (&record.file, record.line) {
+                (Some(file), Some(line)) => format!("{} {}:L{} - ", target, file, line),
+                _ => format!("{} - ", target),
+            },
+            None => String::new(),
+        };
+        let mut fields = String::new();
+        for (key, value) in &record.fields {
+            let _ = write!(fields, " {}={}", key, value);
+        }
+        return format!("{} [{}] {}{}{}", record.now, record.level, location, strip_ansi(&record.message), fields);
+    }
+    let theme = theme();
+    let color_code = theme.level(&record.level);
+    let location = match &record.target {
+        Some(target) => match (&record.file, record.line) {
+            (Some(file), Some(line)) => {
+                format!("{}{}\x1b[0m {}{}:L{}\x1b[0m - ", theme.target, target, theme.location, file, line)
+            }
+            _ => format!("{}{}\x1b[0m - ", theme.target, target),
+        },
+        None => String::new(),
+    };
+    let mut fields = String::new();
+    for (key, value) in &record.fields {
+        let _ = write!(fields, " {}{}\x1b[0m={}", theme.key, key, value);
+    }
+    format!(
+        "{}{}\x1b[0m {}[{}]\x1b[0m {}{}{}\x1b[0m{}",
+        theme.timestamp, record.now, color_code, record.level, location, color_code, record.message, fields
+    )
+}
+
+/// Removes ANSI escape sequences (such as the `\x1b[36m` colour codes) from the string.
+///
+/// ```
+/// use logger_rust::format::strip_ansi;
+///
+/// assert_eq!(strip_ansi("\x1b[1m\x1b[37mbold\x1b[0m text"), "bold text");
+/// ```
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'[') {
+            // CSI sequence: parameters and intermediates, terminated by a byte in `@`..=`~`
+            chars.next();
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            // Two-byte escape sequence
+            chars.next();
+        }
+    }
+    out
+}
+
+fn render_json(record: &Record) -> String {
+//! Renders the record as a single JSON object. Missing `module`, `file` and `line` are written as `null`.
+    let mut out = String::from("{");
+    push_json_key(&mut out, "timestamp", true);
+    push_json_str(&mut out, &record.now);
+    push_json_key(&mut out, "level", false);
+    push_json_str(&mut out, &record.level);
+    push_json_key(&mut out, "message", false);
+    push_json_str(&mut out, &strip_ansi(&record.message));
+    push_json_key(&mut out, "module", false);
+    push_json_opt_str(&mut out, record.module_path.as_deref().or(record.target.as_deref()));
+    push_json_key(&mut out, "file", false);
+    push_json_opt_str(&mut out, record.file.as_deref());
+    push_json_key(&mut out, "line", false);
+    match record.line {
+        Some(line) => { let _ = write!(out, "{}", line); }
+        None => out.push_str("null"),
+    }
+    push_json_key(&mut out, "thread", false);
+    push_json_str(&mut out, &record.thread);
+    if !record.fields.is_empty() {
+        push_json_key(&mut out, "fields", false);
+        out.push('{');
+        for (i, (key, value)) in record.fields.iter().enumerate() {
+            push_json_key(&mut out, key, i == 0);
+            push_json_value(&mut out, value);
+        }
+        out.push('}');
+    }
+    out.push('}');
+    out
+}
+
+fn render_logfmt(record: &Record) -> String {
+//! Renders the record as a logfmt line. The level is lowercased, values are quoted only when needed.
+    let mut out = String::new();
+    push_logfmt_pair(&mut out, "ts", &record.now);
+    push_logfmt_pair(&mut out, "level", &record.level.to_ascii_lowercase());
+    push_logfmt_pair(&mut out, "msg", &strip_ansi(&record.message));
+    if let Some(module) = record.module_path.as_deref().or(record.target.as_deref()) {
+        push_logfmt_pair(&mut out, "module", module);
+    }
+    for (key, value) in &record.fields {
+        push_logfmt_pair(&mut out, key, &value.to_string());
+    }
+    out
+}
+
+fn push_logfmt_pair(out: &mut String, key: &str, value: &str) {
+    if !out.is_empty() {
+        out.push(' ');
+    }
+    out.push_str(key);
+    out.push('=');
+    let needs_quotes = value.is_empty()
+        || value.chars().any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !needs_quotes {
+        out.push_str(value);
+        return;
+    }
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => { let _ = write!(out, "\\u{{{:04x}}}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_json_key(out: &mut String, key: &str, first: bool) {
+    if !first {
+        out.push(',');
+    }
+    push_json_str(out, key);
+    out.push(':');
+}
+
+fn push_json_opt_str(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => push_json_str(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+fn push_json_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Int(i) => { let _ = write!(out, "{}", i); }
+        Value::UInt(u) => { let _ = write!(out, "{}", u); }
+        Value::Float(x) if x.is_finite() => { let _ = write!(out, "{}", x); }
+        Value::Float(x) => push_json_str(out, &x.to_string()),
+        Value::Bool(b) => { let _ = write!(out, "{}", b); }
+        Value::Str(s) | Value::Display(s) | Value::Debug(s) => push_json_str(out, s),
+    }
+}
+
+fn push_json_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
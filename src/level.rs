@@ -0,0 +1,188 @@
+//! # Custom levels
+//! Besides the five built-in severities, levels such as `AUDIT` or `NOTICE` can be registered with a rank and a colour.
+//! The rank places the level among the built-in ones (`ERROR` = 1, `WARN` = 2, `INFO` = 3, `DEBUG` = 4, `TRACE` = 5;
+//! `0` is more important than `ERROR`), so custom levels are filtered by `set_min_severity` and `set_filter`
+//! like the built-in level of the same rank. The colour is used by `Format::Colored`, every other format uses the name.
+//!
+//! `define_level!` generates the `log_<name>!` macro of a level, and a `register_<name>_level()` function.
+//! The level is registered the first time the macro is used; until then, records of that level logged some other way
+//! (`log_message`, the `log` bridge, ...) are neither filtered nor coloured. Call the registration function at startup
+//! to avoid that:
+//! ```
+//! use logger_rust::*;
+//!
+//! define_level!(notice, 3, "\x1b[32m"); // same rank as INFO, green
+//!
+//! assert_eq!(level_rank("NOTICE"), None);
+//! register_notice_level();
+//! assert_eq!(level_rank("NOTICE"), Some(3));
+//! log_notice!("Config reloaded");
+//! log_notice!(version = 7; "Config reloaded");
+//! // 2023-06-09 15:06:46 [NOTICE] Config reloaded
+//!
+//! set_min_severity(Severity::Warn);
+//! log_notice!("Dropped, like INFO records");
+//! # set_min_severity(Severity::Trace);
+//! ```
+use crate::config::{Severity, rank_enabled};
+use crate::error::{read, try_write, write, LoggerError};
+use crate::filter::filter_enabled;
+use std::{fmt, sync::RwLock};
+
+lazy_static::lazy_static! {
+    static ref LEVELS: RwLock<Vec<CustomLevel>> = RwLock::new(Vec::new());
+}
+
+struct CustomLevel {
+    name: String,
+    rank: u8,
+    color: String,
+}
+
+/// A level as the logging macros see it: the name written into the records and the rank used for filtering.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub name: &'static str,
+    pub rank: u8,
+}
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Self {
+        Level {
+            name: severity.as_str(),
+            rank: severity as u8,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Registers (or updates) the custom level `name` with the given rank (`0` to `5`, higher ranks are treated as `5`)
+/// and ANSI colour (e.g. `"\x1b[32m"`). Names are case-insensitive; records carry the upper-cased name.
+///
+/// ```
+/// use logger_rust::*;
+///
+/// register_level("audit", 0, "\x1b[1m\x1b[35m");
+/// log_message("AUDIT", &current_time(), "User 42 deleted order A-17");
+/// ```
+pub fn register_level(name: &str, severity_rank: u8, color: &str) {
+    insert(&mut write(&LEVELS), name, severity_rank.min(Severity::Trace as u8), color, true);
+}
+
+/// Same as `register_level`, but fails with `LoggerError::InvalidConfig` for a rank above `5`, an empty name,
+/// a name containing whitespace or brackets, or the name of a built-in level,
+/// and with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_register_level(name: &str, severity_rank: u8, color: &str) -> Result<(), LoggerError> {
+    if severity_rank > Severity::Trace as u8 {
+        return Err(LoggerError::InvalidConfig(format!(
+            "the rank of level `{}` must be between 0 and {}, got {}",
+            name,
+            Severity::Trace as u8,
+            severity_rank
+        )));
+    }
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '[' || c == ']') {
+        return Err(LoggerError::InvalidConfig(format!("`{}` is not a valid level name", name)));
+    }
+    if Severity::from_level(name).is_some() {
+        return Err(LoggerError::InvalidConfig(format!("`{}` is a built-in level", name)));
+    }
+    insert(&mut *try_write(&LEVELS, "custom levels")?, name, severity_rank, color, true);
+    Ok(())
+}
+
+#[doc(hidden)]
+/// Used by the macros generated by `define_level!`: registers the level unless it already is,
+/// so that an explicit `register_level` call always wins.
+pub fn __register_level_once(name: &str, severity_rank: u8, color: &str) {
+    insert(&mut write(&LEVELS), name, severity_rank.min(Severity::Trace as u8), color, false);
+}
+
+fn insert(levels: &mut Vec<CustomLevel>, name: &str, rank: u8, color: &str, replace: bool) {
+    let name = name.to_ascii_uppercase();
+    match levels.iter_mut().find(|level| level.name == name) {
+        Some(level) if replace => {
+            level.rank = rank;
+            level.color = color.to_string();
+        }
+        Some(_) => {}
+        None => levels.push(CustomLevel { name, rank, color: color.to_string() }),
+    }
+}
+
+/// Returns the rank of a built-in or registered level, `None` for unknown levels.
+pub fn level_rank(level: &str) -> Option<u8> {
+    if let Some(severity) = Severity::from_level(level) {
+        return Some(severity as u8);
+    }
+    read(&LEVELS)
+        .iter()
+        .find(|custom| custom.name.eq_ignore_ascii_case(level))
+        .map(|custom| custom.rank)
+}
+
+/// Returns the colour of a registered custom level.
+pub(crate) fn level_color(level: &str) -> Option<String> {
+    read(&LEVELS)
+        .iter()
+        .find(|custom| custom.name.eq_ignore_ascii_case(level))
+        .map(|custom| custom.color.clone())
+}
+
+/// Returns `true` if records of the given level from the given module pass both the severity threshold and the filter.
+/// Used by the logging macros before formatting anything.
+#[inline]
+pub fn level_enabled(level: Level, module: &str) -> bool {
+    rank_enabled(level.rank) && filter_enabled(level.rank, Some(module))
+}
+
+#[macro_export]
+/// ## Macro rules - define_level!
+/// `define_level!(name, rank, color)` generates a `log_<name>!` macro for the custom level `NAME`
+/// (see `register_level`). The generated macro accepts the same arguments as `log_info!`, including structured fields,
+/// and registers the level the first time it is used.
+///
+/// It also generates a `register_<name>_level()` function that registers the level right away. Call it at startup
+/// if records of that level may be logged before the first `log_<name>!` call (through `log_message` or the bridges),
+/// or they are not filtered by rank and have no colour.
+///
+/// Like any `macro_rules!` macro, the generated one can only be used after the `define_level!` call,
+/// so put it at the top of your crate root.
+macro_rules! define_level {
+    ($name:ident, $rank:expr, $color:expr) => {
+        $crate::__define_level!(($) $name, $rank, $color);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// ## Macro rules - __define_level!
+/// Receives the `$` token from `define_level!`, so that the generated macro can declare its own arguments.
+macro_rules! __define_level {
+    (($d:tt) $name:ident, $rank:expr, $color:expr) => {
+        $crate::paste::paste! {
+            /// Registers the level generated by `define_level!`, see `register_level`.
+            #[allow(dead_code)]
+            pub fn [<register_ $name _level>]() {
+                $crate::register_level(stringify!([<$name:upper>]), $rank, $color);
+            }
+
+            #[allow(unused_macros)]
+            macro_rules! [<log_ $name>] {
+                ($d($d arg:tt)*) => {{
+                    static REGISTERED: ::std::sync::Once = ::std::sync::Once::new();
+                    REGISTERED.call_once(|| $crate::__register_level_once(stringify!([<$name:upper>]), $rank, $color));
+                    $crate::__log!(
+                        $crate::Level { name: stringify!([<$name:upper>]), rank: ::std::cmp::min($rank, 5) },
+                        $d($d arg)*
+                    )
+                }};
+            }
+        }
+    };
+}
@@ -5,7 +5,7 @@
 //! - Log messages of different types (error, warn, info, debug) using macros
 //! - Choose whether to log messages to the console, a file, or both
 //! - Set the log level at runtime
-//! - Automatically create and append to log files based on the current date
+//! - Automatically create and append to a log file named after the process start time (or a fixed name)
 //! - Handle errors when writing to log files
 //!
 //! ## Usage
@@ -79,23 +79,66 @@
 //! }
 //! ```
 //!
+//! To give a custom level a colour and a rank among the built-in ones (so that it is filtered like them),
+//! use `define_level!`, which also generates the `log_<name>!` macro and a `register_<name>_level()` function
+//! to call at startup (see the `level` module):
+//!
+//! ```rust
+//! use logger_rust::*;
+//!
+//! define_level!(notice, 3, "\x1b[32m");
+//! register_notice_level();
+//! log_notice!("Config reloaded");
+//! ```
+//!
 //! 
 //!
 
+#[doc(hidden)]
+pub use paste;
 pub mod time;
+pub mod error;
+pub mod sink;
+pub mod filter;
+pub mod level;
+pub mod theme;
+pub mod env;
+pub mod record;
+pub mod format;
+pub mod async_writer;
 pub mod config;
 pub mod set_log;
 pub mod log_file;
 pub mod log_rotator;
 pub mod tracer_config;
+#[cfg(feature = "log")]
+pub mod log_bridge;
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
 pub use crate::set_log::*;
-pub use crate::log_rotator::*;
 pub use crate::tracer_config::*;
 pub use crate::config::LOG_PATH;
-pub use crate::time::current_time;
-pub use crate::log_file::log_message;
-pub use crate::set_log::{set_log_level, set_log_path};
-pub use crate::config::{LogVariables, LogVariablesImpl, LogLevel};
+pub use crate::time::{current_time, TimestampFormat, TimestampZone, FixedOffset, set_timestamp_format, try_set_timestamp_format, set_timestamp_zone, try_set_timestamp_zone};
+pub use crate::log_file::{log_message, log_record};
+pub use crate::format::{Format, set_console_format, set_file_format, try_set_console_format, try_set_file_format, console_format, file_format};
+pub use crate::record::{Record, Value};
+pub use crate::async_writer::{AsyncConfig, OverflowPolicy, enable_async, try_enable_async, disable_async, async_enabled, flush_async, dropped_records};
+pub use crate::sink::capture::{CaptureSink, capture_logs};
+#[cfg(unix)]
+pub use crate::sink::syslog::{SyslogSink, SyslogFormat, Facility, DEFAULT_SYSLOG_PATH};
+pub use crate::sink::{Sink, ConsoleSink, FileSink, add_sink, try_add_sink, register_sink_type, clear_sinks, try_clear_sinks, flush_sinks};
+pub use crate::env::init_from_env;
+pub use crate::config::file::{load_config, load_config_str};
+pub use crate::theme::{Theme, ColorMode, set_theme, try_set_theme, theme, set_color_mode, colors_enabled};
+pub use crate::level::{Level, register_level, try_register_level, level_rank, level_enabled, __register_level_once};
+pub use crate::filter::{Filter, set_filter, try_set_filter, clear_filter, module_enabled};
+pub use crate::error::{LoggerError, set_error_handler, reset_error_handler};
+pub use crate::set_log::{set_log_level, try_set_log_level, set_log_path, try_set_log_path, try_set_log_path_or_create, set_flush_policy, try_set_flush_policy, set_log_file_name, try_set_log_file_name};
+#[cfg(feature = "log")]
+pub use crate::log_bridge::{LogBridge, init_log_bridge};
+#[cfg(feature = "tracing")]
+pub use crate::tracing_layer::LoggerLayer;
+pub use crate::config::{LogVariables, LogVariablesImpl, LogLevel, FlushPolicy, LogFileName, Severity, set_min_severity, min_severity, severity_enabled};
 
 pub fn error(now: &str, message: &str) {
 //! # Error
@@ -166,45 +209,61 @@ pub fn trace(now: &str, message: &str) {
 #[macro_export]
 /// ## Macro rules - log_error!
 /// The log_error macro takes any number of arguments and formats them using the format! macro. 
-/// It then gets the current time using the current_time function from the crate and calls the error function from the crate with the current time and formatted message.
+/// It then gets the current time using the current_time function from the crate and logs the formatted message at the `ERROR` level.
+/// Structured fields can be put in front of the message, separated from it by a `;`:
+/// `log_error!(user_id = 42, order = %id, request = ?req; "order placed")`.
+/// `%` captures a value through `Display`, `?` through `Debug`, and plain values are converted with `Value::from`.
 macro_rules! log_error {
-    ($($arg:tt)*) => {{
-        let now = $crate::current_time();
-        $crate::error(&now, &format!($($arg)*));
-    }}
+    ($($arg:tt)*) => {
+        $crate::__log!($crate::Severity::Error, $($arg)*)
+    };
 }
 
 #[macro_export]
 /// ## Macro rules - log_warn!
 /// The log_warn macro takes any number of arguments and formats them using the format! macro. 
-/// It then gets the current time using the current_time function from the crate and calls the warn function from the crate with the current time and formatted message.
+/// It then gets the current time using the current_time function from the crate and logs the formatted message at the `WARN` level.
+/// Structured fields can be put in front of the message, separated from it by a `;`:
+/// `log_warn!(user_id = 42, order = %id, request = ?req; "order placed")`.
+/// `%` captures a value through `Display`, `?` through `Debug`, and plain values are converted with `Value::from`.
 macro_rules! log_warn {
-    ($($arg:tt)*) => {{
-        let now = $crate::current_time();
-        $crate::warn(&now, &format!($($arg)*));
-    }}
+    ($($arg:tt)*) => {
+        $crate::__log!($crate::Severity::Warn, $($arg)*)
+    };
 }
 
 #[macro_export]
 /// ## Macro rules - log_info!
 /// The log_info macro takes any number of arguments and formats them using the format! macro. 
-/// It then gets the current time using the current_time function from the crate and calls the info function from the crate with the current time and formatted message.
+/// It then gets the current time using the current_time function from the crate and logs the formatted message at the `INFO` level.
+/// Structured fields can be put in front of the message, separated from it by a `;`:
+/// `log_info!(user_id = 42, order = %id, request = ?req; "order placed")`.
+/// `%` captures a value through `Display`, `?` through `Debug`, and plain values are converted with `Value::from`.
+///
+/// ```
+/// use logger_rust::*;
+///
+/// let order_id = "A-17";
+/// log_info!(user_id = 42, order = %order_id, paid = true; "order placed by {}", "alice");
+/// // 2023-06-09 15:06:46 [INFO] order placed by alice user_id=42 order=A-17 paid=true
+/// ```
 macro_rules! log_info {
-    ($($arg:tt)*) => {{
-        let now = $crate::current_time();
-        $crate::info(&now, &format!($($arg)*));
-    }}
+    ($($arg:tt)*) => {
+        $crate::__log!($crate::Severity::Info, $($arg)*)
+    };
 }
 
 #[macro_export]
 /// ## Macro rules - log_debug!
 /// The log_debug macro takes any number of arguments and formats them using the format! macro. 
-/// It then gets the current time using the current_time function from the crate and calls the debug function from the crate with the current time and formatted message.
+/// It then gets the current time using the current_time function from the crate and logs the formatted message at the `DEBUG` level.
+/// Structured fields can be put in front of the message, separated from it by a `;`:
+/// `log_debug!(user_id = 42, order = %id, request = ?req; "order placed")`.
+/// `%` captures a value through `Display`, `?` through `Debug`, and plain values are converted with `Value::from`.
 macro_rules! log_debug {
-    ($($arg:tt)*) => {{
-        let now = $crate::current_time();
-        $crate::debug(&now, &format!($($arg)*));
-    }}
+    ($($arg:tt)*) => {
+        $crate::__log!($crate::Severity::Debug, $($arg)*)
+    };
 }
 
 #[macro_export]
@@ -218,8 +277,12 @@ macro_rules! log_debug {
 ///
 /// 2. `log_trace!(debug_object, context)`: This logs the same information as the first form, but also includes a context string that can provide additional information about the log message.
 ///
+/// The output of the first two forms can be customised with `set_tracer_config` (see `TracerConfiguration`).
+///
 /// 3. `log_trace!(format_string, args...)`: This logs a formatted message using the given format string and arguments. The format string should follow the same syntax as the standard `format!` macro.
 ///
+/// 4. `log_trace!(key = value, ...; format_string, args...)`: Same as the third form, with structured fields (see `log_info!`).
+///
 /// ## Examples
 ///
 /// ```rust
@@ -233,43 +296,82 @@ macro_rules! log_debug {
 /// log_trace!(x, "{}"); // Logs: "TRACE used: x ->> (42): 42 | Type: <i32> ... <context is empty>"
 /// ```
 macro_rules! log_trace {
+    ($key:ident = $($rest:tt)+) => {
+        $crate::__log!($crate::Severity::Trace, $key = $($rest)+)
+    };
     ($debug_object:expr) => {{
-        log_trace!($debug_object, "");
+        $crate::log_trace!($debug_object, "");
     }};
     ($debug_object:expr, $context:expr) => {{
-        let now = $crate::current_time();
-        let line = line!();
-        let file = file!();
-        let module_path = module_path!();
-        let thread_id = std::thread::current().id();
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_micros();
-        let context_str = if $context.is_empty() {
-            String::new()
-        } else {
-            format!("\x1b[36m ->> Context: \x1b[0m\x1b[1m<{}>", $context)
-        };
-        let type_name = $debug_object.type_name();
-        let column = column!();
-        let debug_info = format!(
-            "\x1b[34m{}:L{}/C{} - used: \x1b[32m{}\x1b[36m ->> ({:?}): \x1b[31m{:?}\x1b[36m | \x1b[32mType: \x1b[0m\x1B[1m<{}>\x1b[0m | \x1b[32m{:?} \x1b[36m->> \x1b[34mTimestamp: UN{}IX{}\x1b[0m\x1b[36m |\x1b[33m Module: \x1b[0m{}",
-            file, line,
-            column,
-            stringify!($debug_object), 
-            &$debug_object, 
-            $debug_object,
-            type_name,
-            thread_id,
-            timestamp,
-            context_str,
-            module_path,
-        );
-        $crate::log_message("TRACE", &now, &debug_info);
+        if $crate::module_enabled($crate::Severity::Trace, module_path!()) {
+            let now = $crate::current_time();
+            let value = format!("{:?}", &$debug_object);
+            let context: &str = &$context;
+            let info = $crate::TraceInfo {
+                file: file!(),
+                line: line!(),
+                column: column!(),
+                module_path: module_path!(),
+                expression: stringify!($debug_object),
+                value: &value,
+                type_name: $crate::TypeName::type_name(&$debug_object),
+                context,
+            };
+            let mut record = $crate::Record::new("TRACE", &now, &$crate::render_trace(&info));
+            record.module_path = Some(info.module_path.to_string());
+            record.file = Some(info.file.to_string());
+            record.line = Some(info.line);
+            $crate::log_record(record);
+        }
     }};
-    ($($arg:tt)*) => {{
-        let now = $crate::current_time();
-        $crate::log_message("TRACE", &now, &format!($($arg)*));
-    }}
-}
\ No newline at end of file
+    ($($arg:tt)*) => {
+        $crate::__log!($crate::Severity::Trace, $($arg)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// ## Macro rules - __log!
+/// Shared implementation of the logging macros. Munches the optional `key = value` fields
+/// in front of the `;`, then checks the severity and builds the `Record`.
+macro_rules! __log {
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = % $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $severity, [$($fields,)* (stringify!($key), $crate::Value::Display(format!("{}", $value)))], $($rest)+)
+    };
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = % $value:expr; $($arg:tt)+) => {
+        $crate::__log!(@emit $severity, [$($fields,)* (stringify!($key), $crate::Value::Display(format!("{}", $value)))], $($arg)+)
+    };
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = ? $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $severity, [$($fields,)* (stringify!($key), $crate::Value::Debug(format!("{:?}", $value)))], $($rest)+)
+    };
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = ? $value:expr; $($arg:tt)+) => {
+        $crate::__log!(@emit $severity, [$($fields,)* (stringify!($key), $crate::Value::Debug(format!("{:?}", $value)))], $($arg)+)
+    };
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $severity, [$($fields,)* (stringify!($key), $crate::Value::from($value))], $($rest)+)
+    };
+    (@fields $severity:expr, [$($fields:expr),*], $key:ident = $value:expr; $($arg:tt)+) => {
+        $crate::__log!(@emit $severity, [$($fields,)* (stringify!($key), $crate::Value::from($value))], $($arg)+)
+    };
+    (@emit $severity:expr, [$($fields:expr),*], $($arg:tt)+) => {{
+        let level: $crate::Level = $crate::Level::from($severity);
+        if $crate::level_enabled(level, module_path!()) {
+            let now = $crate::current_time();
+            let mut record = $crate::Record::new(level.name, &now, &format!($($arg)+));
+            record.module_path = Some(module_path!().to_string());
+            record.file = Some(file!().to_string());
+            record.line = Some(line!());
+            record.fields = vec![$({
+                let (key, value): (&str, $crate::Value) = $fields;
+                (key.to_string(), value)
+            }),*];
+            $crate::log_record(record);
+        }
+    }};
+    ($severity:expr, $key:ident = $($rest:tt)+) => {
+        $crate::__log!(@fields $severity, [], $key = $($rest)+)
+    };
+    ($severity:expr, $($arg:tt)+) => {
+        $crate::__log!(@emit $severity, [], $($arg)+)
+    };
+}
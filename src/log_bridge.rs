@@ -0,0 +1,69 @@
+//! # log bridge
+//! Available with the `log` cargo feature.
+//! Implements the `log::Log` trait on top of `log_record`, so crates that log through
+//! `log::info!` and friends end up in the same console/file output and sinks as `log_info!`.
+//!
+//! ```
+//! use logger_rust::*;
+//!
+//! init_log_bridge().unwrap();
+//! log::warn!(target: "db", "Connection pool is exhausted");
+//! // 2023-06-09 15:06:46 [WARN] db src/main.rs:L5 - Connection pool is exhausted
+//! ```
+use crate::config::Severity;
+use crate::filter::module_enabled;
+use crate::log_file::log_record;
+use crate::record::Record;
+use crate::sink::flush_sinks;
+use crate::time::current_time;
+
+/// The `log::Log` implementation installed by `init_log_bridge`.
+pub struct LogBridge;
+
+static LOG_BRIDGE: LogBridge = LogBridge;
+
+/// Maps a `log::Level` to the matching `Severity`.
+fn severity_of(level: log::Level) -> Severity {
+    match level {
+        log::Level::Error => Severity::Error,
+        log::Level::Warn => Severity::Warn,
+        log::Level::Info => Severity::Info,
+        log::Level::Debug => Severity::Debug,
+        log::Level::Trace => Severity::Trace,
+    }
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        module_enabled(severity_of(metadata.level()), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut bridged = Record::new(
+            severity_of(record.level()).as_str(),
+            &current_time(),
+            &record.args().to_string(),
+        );
+        bridged.target = Some(record.target().to_string());
+        bridged.module_path = record.module_path().map(str::to_string);
+        bridged.file = record.file().map(str::to_string);
+        bridged.line = record.line();
+        log_record(bridged);
+    }
+
+    fn flush(&self) {
+        flush_sinks();
+    }
+}
+
+/// Installs `LogBridge` as the global logger of the `log` crate.
+/// Fails if another logger has already been installed.
+/// Filtering is done by `set_min_severity` and `set_filter` (matching the `target`), so the `log` max level is set to `Trace`.
+pub fn init_log_bridge() -> Result<(), log::SetLoggerError> {
+    log::set_logger(&LOG_BRIDGE)?;
+    log::set_max_level(log::LevelFilter::Trace);
+    Ok(())
+}
@@ -2,91 +2,306 @@ pub use crate::config::{
     LogVariables, 
     LogVariablesImpl, 
     LogLevel,
-    LOG_PATH, LOG_ROTATOR_CONFIG
+    Severity,
+    severity_enabled,
+    FlushPolicy,
+    LogFileName,
+    LOG_PATH, LOG_ROTATOR_CONFIG, FLUSH_POLICY, LOG_FILE_NAME
 };
 use std::{
     fs,
-    path::Path,
-    fs::OpenOptions,
-    io::{self, Write},
-
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    io::{self, BufWriter, Write},
+    sync::Mutex,
+    sync::mpsc::{self, SendError, Sender},
+    thread,
+    time::{Instant, SystemTime},
 };
-use chrono::Local;
+use crate::log_rotator::{Compression, LogRotatorConfig};
+use chrono::{DateTime, Local, NaiveDateTime};
+use crate::record::Record;
+use crate::sink::dispatch;
+use crate::async_writer::submit;
+use crate::filter::record_enabled;
+use crate::sink::capture::capture;
+use crate::error::{lock, report_error, LoggerError};
 
 pub fn log_to_file(now: &str, message: &str) -> io::Result<()> {
 //! # log_to_file
 //! The `log_to_file` function takes two arguments: `now` and `message`. 
 //! - The `now` argument is a string representing the current time and the message argument is the message to be logged. 
-//! The function checks if the log path is empty. If it is, it creates a new filename using the current date. If the log path is not empty, 
-//! it joins the log path with the filename. 
-//! The function then creates a new file at the specified path using the `OpenOptions` struct and writes the message to the file.
-    let log_path = LOG_PATH.lock().unwrap();
-    
-    let filename = if log_path.as_os_str().is_empty() {
-        format!("{}.log", Local::now().format("%Y-%m-%d@%H-%M-%S"))
-    } else {
-        log_path.join(format!("{}.log", Local::now().format("%Y-%m-%d@%H-%M-%S"))).to_string_lossy().into_owned()
+//!
+//! The message is written as a `now message` line, see `append_to_file` for details.
+    append_to_file(&format!("{} {}", now, message))
+}
+
+pub fn append_to_file(line: &str) -> io::Result<()> {
+//! # append_to_file
+//! Appends an already rendered line to the current log file.
+//!
+//! The function checks if the log path is empty. If it is, the file is created in the current directory. If the log path is not empty, 
+//! it joins the log path with the file name (see `LogFileName`). 
+//! The file is kept open in a buffered writer between calls, and is only reopened when it gets rotated or when its path changes.
+//! Whether the line is flushed right away depends on the current `FlushPolicy`.
+    write_line(line, None)
+}
+
+/// The currently open log file.
+struct ActiveFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    last_write: SystemTime,
+    last_flush: Instant,
+    /// Start of the rotation period the file belongs to, if a `RotationSchedule` is configured.
+    period_start: Option<NaiveDateTime>,
+}
+
+lazy_static::lazy_static! {
+    static ref ACTIVE_FILE: Mutex<Option<ActiveFile>> = Mutex::new(None);
+    /// Sends the jobs to the compression worker, started on the first rotation. A single worker runs the jobs
+    /// one after the other, so that retention never sees a file that is being compressed.
+    static ref COMPRESSOR: Mutex<Option<Sender<CompressionJob>>> = Mutex::new(None);
+}
+
+/// Writes the line to the active log file, rotating and reopening it if necessary,
+/// and flushes according to the current `FlushPolicy`. `severity` is the severity of the record the line belongs to, if known.
+pub(crate) fn write_line(line: &str, severity: Option<Severity>) -> io::Result<()> {
+    let mut errors = Vec::new();
+    let result = write_line_locked(line, severity, &mut errors);
+    // Reported once the locks are released, so that the error handler can log
+    errors.into_iter().for_each(report_error);
+    result
+}
+
+/// Does the work of `write_line`, collecting the rotation errors into `errors`.
+fn write_line_locked(line: &str, severity: Option<Severity>, errors: &mut Vec<LoggerError>) -> io::Result<()> {
+    let path = {
+        let log_path = lock(&LOG_PATH);
+        let filename = lock(&LOG_FILE_NAME).file_name();
+        if log_path.as_os_str().is_empty() {
+            PathBuf::from(filename)
+        } else {
+            log_path.join(filename)
+        }
     };
-    
-    let path = Path::new(&filename);
-    
+
+    let mut active = lock(&ACTIVE_FILE);
+    if active.as_ref().is_some_and(|file| file.path != path) {
+        if let Some(mut previous) = active.take() {
+            previous.writer.flush()?;
+        }
+    }
+
     // Check if we need to rotate the logs
-    if let Some(log_rotator_config) = &*LOG_ROTATOR_CONFIG.lock().unwrap() {
-        // Check if the current log file has exceeded the maximum size or lifetime
-        if let Ok(metadata) = fs::metadata(&path) {
-            if metadata.len() > log_rotator_config.max_size
-                || metadata.modified()?.elapsed().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-                    > log_rotator_config.max_time
-            {
-                // Rotate the logs
-                let mut i = 1;
-                loop {
-                    let rotated_filename = format!("{}_rot-{}.log", filename.trim_end_matches(".log"), i);
-                    let rotated_path = Path::new(&rotated_filename);
-                    if !rotated_path.exists() {
-                        fs::rename(&path, rotated_path)?;
-                        break;
+    if let Some(log_rotator_config) = &*lock(&LOG_ROTATOR_CONFIG) {
+        let (size, last_write) = match active.as_ref() {
+            Some(file) => (Some(file.size), Some(file.last_write)),
+            None => match fs::metadata(&path) {
+                Ok(metadata) if metadata.len() > 0 => (Some(metadata.len()), Some(metadata.modified()?)),
+                _ => (None, None),
+            },
+        };
+        // The period the records of the current file belong to, for calendar-aligned rotation
+        let period = match (log_rotator_config.schedule, active.as_ref()) {
+            (Some(_), Some(file)) => file.period_start,
+            (Some(schedule), None) => last_write.map(|last_write| schedule.period_start(DateTime::<Local>::from(last_write).naive_local())),
+            (None, _) => None,
+        };
+        let now = Local::now().naive_local();
+        let label = match (log_rotator_config.schedule, period) {
+            (Some(schedule), Some(period)) if schedule.period_start(now) != period => Some(schedule.label(period)),
+            _ => None,
+        };
+        // Check if the current log file has exceeded the maximum size or lifetime, or if its period is over
+        let exceeded = match (size, last_write) {
+            (Some(size), Some(last_write)) => {
+                size > log_rotator_config.max_size
+                    || last_write.elapsed().unwrap_or_default() > log_rotator_config.max_time
+            }
+            _ => false,
+        };
+        if label.is_some() || exceeded {
+            if let Some(mut file) = active.take() {
+                file.writer.flush()?;
+            }
+            // A failed rotation is reported, and the records keep going to the current file
+            match rotate(&path, label.as_deref()) {
+                Ok(_) if log_rotator_config.compression == Compression::None => {
+                    if let Err(e) = log_rotator_config.apply_retention(&path) {
+                        errors.push(LoggerError::Rotation(path.clone(), e));
                     }
-                    i += 1;
                 }
+                Ok(rotated_path) => compress_in_background(
+                    CompressionJob {
+                        config: log_rotator_config.clone(),
+                        rotated_path,
+                        active_path: path.clone(),
+                    },
+                    errors,
+                ),
+                Err(e) => errors.push(LoggerError::Rotation(path.clone(), e)),
             }
         }
     }
-    
-    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
-    writeln!(file, "{} {}", now, message)?;
+
+    if active.is_none() {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        *active = Some(ActiveFile {
+            path,
+            writer: BufWriter::new(file),
+            size,
+            last_write: SystemTime::now(),
+            last_flush: Instant::now(),
+            period_start: None,
+        });
+    }
+
+    let file = active.as_mut().expect("the log file was opened above");
+    if file.period_start.is_none() {
+        if let Some(schedule) = lock(&LOG_ROTATOR_CONFIG).as_ref().and_then(|config| config.schedule) {
+            file.period_start = Some(schedule.period_start(Local::now().naive_local()));
+        }
+    }
+    writeln!(file.writer, "{}", line)?;
+    file.size += line.len() as u64 + 1;
+    file.last_write = SystemTime::now();
+
+    let flush = match *lock(&FLUSH_POLICY) {
+        FlushPolicy::EveryRecord => true,
+        FlushPolicy::Interval(interval) => file.last_flush.elapsed() >= interval,
+        // Lines of unknown importance (e.g. from `append_to_file`) are flushed
+        FlushPolicy::MinSeverity(min) => severity.is_none_or(|severity| severity <= min),
+    };
+    if flush {
+        file.writer.flush()?;
+        file.last_flush = Instant::now();
+    }
     Ok(())
 }
 
+/// A rotated file waiting to be compressed, followed by the retention of its log file.
+struct CompressionJob {
+    config: LogRotatorConfig,
+    rotated_path: PathBuf,
+    active_path: PathBuf,
+}
+
+impl CompressionJob {
+    fn run(self) {
+        // Retention may already have deleted the file, if an older job kept it waiting
+        if self.rotated_path.exists() {
+            if let Err(e) = self.config.compression.compress(&self.rotated_path) {
+                report_error(LoggerError::Rotation(self.rotated_path, e));
+            }
+        }
+        self.config
+            .apply_retention(&self.active_path)
+            .unwrap_or_else(|e| report_error(LoggerError::Rotation(self.active_path.clone(), e)));
+    }
+}
+
+/// Hands the job to the compression worker, off the hot path. If the worker cannot be started,
+/// the error is collected into `errors` and the rotated file is left uncompressed.
+fn compress_in_background(job: CompressionJob, errors: &mut Vec<LoggerError>) {
+    let mut compressor = lock(&COMPRESSOR);
+    // A worker that died (e.g. after a panic) is replaced
+    let job = match compressor.as_ref() {
+        Some(sender) => match sender.send(job) {
+            Ok(()) => return,
+            Err(SendError(job)) => job,
+        },
+        None => job,
+    };
+    let (sender, receiver) = mpsc::channel::<CompressionJob>();
+    let spawned = thread::Builder::new()
+        .name("logger-rust-compressor".to_string())
+        .spawn(move || receiver.into_iter().for_each(CompressionJob::run));
+    match spawned {
+        Ok(_) => {
+            let _ = sender.send(job);
+            *compressor = Some(sender);
+        }
+        Err(e) => {
+            *compressor = None;
+            errors.push(LoggerError::Spawn(e));
+            let CompressionJob { config, active_path, .. } = job;
+            if let Err(e) = config.apply_retention(&active_path) {
+                errors.push(LoggerError::Rotation(active_path, e));
+            }
+        }
+    }
+}
+
+/// Flushes the buffered writer of the active log file, if any.
+pub(crate) fn flush_file() -> io::Result<()> {
+    if let Some(file) = lock(&ACTIVE_FILE).as_mut() {
+        file.writer.flush()?;
+        file.last_flush = Instant::now();
+    }
+    Ok(())
+}
+
+/// Renames the log file and returns the new path. Without a `label`, the first free `<name>_rot-N.<ext>` name is used;
+/// with a period `label`, the file becomes `<name>_<label>.<ext>` (or `<name>_<label>-N.<ext>` if that one is taken).
+/// Compressed files are taken into account when looking for a free name.
+fn rotate(path: &Path, label: Option<&str>) -> io::Result<PathBuf> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| ext.to_string_lossy()).unwrap_or_else(|| "log".into());
+    let mut i = match label {
+        Some(_) => 0,
+        None => 1,
+    };
+    loop {
+        let rotated_name = match (label, i) {
+            (Some(label), 0) => format!("{}_{}", stem, label),
+            (Some(label), i) => format!("{}_{}-{}", stem, label, i),
+            (None, i) => format!("{}_rot-{}", stem, i),
+        };
+        let rotated_path = path.with_file_name(format!("{}.{}", rotated_name, extension));
+        let taken = rotated_path.exists()
+            || Compression::EXTENSIONS.iter().any(|compressed| {
+                path.with_file_name(format!("{}.{}.{}", rotated_name, extension, compressed)).exists()
+            });
+        if !taken {
+            fs::rename(path, &rotated_path)?;
+            return Ok(rotated_path);
+        }
+        i += 1;
+    }
+}
+
 pub fn log_message(level: &str, now: &str, message: &str) {
 //! # log_message
 //! The log_message function takes three arguments: level, now, and message. 
 //! - The `level` argument is a string representing the log level *(e.g. “ERROR”, “WARN”, “INFO”, “DEBUG”)*. 
 //! - The `now` argument is a string representing the current time and the message argument is the message to be logged. 
-//! - The `function` matches the log level with a color code and formats the message with the color code and log level. 
-//! It then checks the current log level and logs the message to either the console, a file, or both depending on the current log level.
-    let color_code = match level {
-        "ERROR" => "\x1b[31m\x1b[1m", // red
-        "WARN" => "\x1b[33m",  // yellow
-        "INFO" => "\x1b[36m",  // cyan
-        "DEBUG" => "\x1b[34m", // blue
-        "TRACE" => "\x1b[35m", // idk
-        _ => "\x1b[0m",        // reset
-    };
-    let message = format!(
-        "\x1b[1m\x1b[37m{}\x1b[0m {}[{}]\x1b[0m {}{}\x1b[0m",
-        now, color_code, level, color_code, message
-    );
-    let log_variables = LogVariablesImpl;
-    let log_level = log_variables.log_level().lock().unwrap();
-    match *log_level {
-        LogLevel::Console => eprintln!("{}", message),
-        LogLevel::File => {
-            log_to_file(now, &message).unwrap_or_else(|e| eprintln!("Failed to write to log file: {}", e));
-        }
-        LogLevel::Both => {
-            eprintln!("{}", message);
-            log_to_file(now, &message).unwrap_or_else(|e| eprintln!("Failed to write to log file: {}", e));
-        }
+//!
+//! Records whose level is less important than the current `min_severity`, or that are rejected by the `Filter`, are dropped right away.
+//! Then it checks the current log level and logs the message to either the console, a file, or both depending on the current log level,
+//! and passes it to every sink registered with `add_sink`.
+    log_record(Record::new(level, now, message));
+}
+
+pub fn log_record(record: Record) {
+//! # log_record
+//! Same as `log_message`, but takes an already built `Record`, so callers can attach
+//! additional information such as the target, file and line.
+//! In asynchronous mode (see `enable_async`) the record is queued for the writer thread instead of being written right away.
+//! ```
+//! use logger_rust::*;
+//!
+//! let mut record = Record::new("WARN", &current_time(), "Disk is almost full");
+//! record.target = Some("storage".to_string());
+//! log_record(record);
+//! ```
+    if !record_enabled(&record) {
+        return;
+    }
+    capture(&record);
+    if let Some(record) = submit(record) {
+        dispatch(&record);
     }
 }
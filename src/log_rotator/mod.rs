@@ -3,6 +3,10 @@
 /// - log_path: path to log directory;
 /// - max_size (`u64`): maximum size of log file;
 /// - max_life (`std::time::Duration`): maximum lifetime of log file;
+///
+/// Rotated files can be cleaned up automatically with the retention settings
+/// (`with_max_files`, `with_max_age` and `with_max_total_size`), and compressed with `with_compression`.
+/// `with_schedule` additionally rotates the file on wall-clock boundaries (every minute, hour, day or week).
 /// 
 /// Here's an example:
 /// ```rust
@@ -21,11 +25,14 @@
 ///     //)));
 /// }
 /// ```
-
 use std::{
-    time::Duration,
-    path::PathBuf,
+    fs,
+    io,
+    time::{Duration, SystemTime},
+    path::{Path, PathBuf},
 };
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike};
+pub use chrono::Weekday;
 pub enum LogConfig {
     /// # LogConfig enum
     /// Defines the Path variable as std::PathBuf for path
@@ -41,15 +48,168 @@ pub enum LogPath {
     Path(PathBuf),
 }
 
+#[derive(Clone, Debug)]
 pub struct LogRotatorConfig {
     /// # LogRotatorConfig struct
     /// Defines the public variables for LogRotator instanse
     /// `log_path` = string
     /// `max-size` = u64 (e.g 5 * 1024 * 1024)
     /// `max_time` = time::Duration
+    /// `max_files` = maximum number of rotated files to keep (`None` = unlimited)
+    /// `max_age` = maximum age of rotated files (`None` = unlimited)
+    /// `max_total_size` = maximum total size of the active and rotated files, in bytes (`None` = unlimited)
+    /// `compression` = how rotated files are compressed (`Compression::None` by default)
+    /// `schedule` = calendar-aligned rotation schedule (`None` = only `max_size` and `max_time` are used)
     pub log_path: PathBuf,
     pub max_size: u64,
     pub max_time: Duration,
+    pub max_files: Option<usize>,
+    pub max_age: Option<Duration>,
+    pub max_total_size: Option<u64>,
+    pub compression: Compression,
+    pub schedule: Option<RotationSchedule>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RotationSchedule {
+    /// # RotationSchedule enum
+    /// Defines wall-clock aligned rotation periods. When a record is written after the end of the period
+    /// the active file belongs to, the file is rotated and the period is encoded in the rotated file name:
+    /// - `Minutely` rotates at every full minute (`app_2023-06-04T12-30.log`);
+    /// - `Hourly` rotates at every full hour (`app_2023-06-04T12.log`);
+    /// - `Daily` rotates every day at `hour:minute` local time (`app_2023-06-04.log`);
+    /// - `Weekly` rotates every week on `weekday` at `hour:minute` local time (`app_2023-W22.log`).
+    Minutely,
+    Hourly,
+    Daily { hour: u32, minute: u32 },
+    Weekly { weekday: Weekday, hour: u32, minute: u32 },
+}
+
+impl RotationSchedule {
+    pub fn period_start(&self, at: NaiveDateTime) -> NaiveDateTime {
+        //! Returns the start of the period `at` belongs to.
+        //! ```rust
+        //! use logger_rust::*;
+        //! use chrono::NaiveDate;
+        //!
+        //! let at = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap().and_hms_opt(1, 15, 0).unwrap();
+        //! let daily = RotationSchedule::Daily { hour: 3, minute: 0 };
+        //! // Before 03:00, the record still belongs to the period that started yesterday
+        //! assert_eq!(daily.period_start(at), NaiveDate::from_ymd_opt(2023, 6, 3).unwrap().and_hms_opt(3, 0, 0).unwrap());
+        //! ```
+        let at_time = |date: chrono::NaiveDate, hour: u32, minute: u32| {
+            date.and_hms_opt(hour.min(23), minute.min(59), 0).unwrap_or_else(|| date.and_time(NaiveTime::MIN))
+        };
+        match *self {
+            RotationSchedule::Minutely => at_time(at.date(), at.hour(), at.minute()),
+            RotationSchedule::Hourly => at_time(at.date(), at.hour(), 0),
+            RotationSchedule::Daily { hour, minute } => {
+                let start = at_time(at.date(), hour, minute);
+                if start > at { start - chrono::Duration::days(1) } else { start }
+            }
+            RotationSchedule::Weekly { weekday, hour, minute } => {
+                let days_back = (7 + at.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+                let start = at_time(at.date() - chrono::Duration::days(days_back as i64), hour, minute);
+                if start > at { start - chrono::Duration::days(7) } else { start }
+            }
+        }
+    }
+
+    pub fn label(&self, period_start: NaiveDateTime) -> String {
+        //! Returns the label of the period starting at `period_start`, as used in rotated file names.
+        let format = match self {
+            RotationSchedule::Minutely => "%Y-%m-%dT%H-%M",
+            RotationSchedule::Hourly => "%Y-%m-%dT%H",
+            RotationSchedule::Daily { .. } => "%Y-%m-%d",
+            RotationSchedule::Weekly { .. } => "%G-W%V",
+        };
+        period_start.format(format).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// # Compression enum
+    /// Defines how rotated files are compressed. Compression runs in a background thread right after the rotation,
+    /// the compressed file gets an additional `.gz` (or `.zst`) extension, e.g. `app_rot-1.log.gz`.
+    /// - `None` leaves rotated files as plain text;
+    /// - `Gzip` compresses them with gzip (requires the `gzip` feature, enabled by default);
+    /// - `Zstd` compresses them with zstd (requires the `zstd` feature).
+    ///
+    /// Every variant exists whatever the features; `try_set_log_path` rejects a compression whose feature is disabled.
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Extensions added to compressed files, used to recognise them.
+    pub(crate) const EXTENSIONS: [&'static str; 2] = ["gz", "zst"];
+
+    pub fn extension(&self) -> Option<&'static str> {
+        //! Returns the extension added to compressed files, `None` for `Compression::None`.
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    pub fn missing_feature(&self) -> Option<&'static str> {
+        //! Returns the cargo feature the compression requires, if it is not enabled.
+        //! ```rust
+        //! use logger_rust::*;
+        //!
+        //! assert_eq!(Compression::None.missing_feature(), None);
+        //! assert_eq!(Compression::Zstd.missing_feature().is_some(), !cfg!(feature = "zstd"));
+        //! ```
+        match self {
+            Compression::Gzip if !cfg!(feature = "gzip") => Some("gzip"),
+            Compression::Zstd if !cfg!(feature = "zstd") => Some("zstd"),
+            _ => None,
+        }
+    }
+
+    pub fn compress(&self, path: &Path) -> io::Result<PathBuf> {
+        //! Compresses the file at `path` into `<path>.gz` (or `.zst`) and removes the original.
+        //! Returns the path of the compressed file (`path` itself for `Compression::None`).
+        //! Fails with `io::ErrorKind::Unsupported` if the feature of the compression is disabled.
+        let Some(extension) = self.extension() else {
+            return Ok(path.to_path_buf());
+        };
+        if let Some(feature) = self.missing_feature() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} compression requires the `{}` cargo feature", feature, feature),
+            ));
+        }
+        let mut compressed_name = path.as_os_str().to_owned();
+        compressed_name.push(".");
+        compressed_name.push(extension);
+        let compressed_path = PathBuf::from(compressed_name);
+
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut source = fs::File::open(path)?;
+                let target = fs::File::create(&compressed_path)?;
+                let mut encoder = flate2::write::GzEncoder::new(target, flate2::Compression::default());
+                io::copy(&mut source, &mut encoder)?;
+                encoder.finish()?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let source = fs::File::open(path)?;
+                let target = fs::File::create(&compressed_path)?;
+                zstd::stream::copy_encode(source, target, 0)?;
+            }
+            // Checked above
+            _ => {}
+        }
+        fs::remove_file(path)?;
+        Ok(compressed_path)
+    }
 }
 
 impl LogRotatorConfig {
@@ -64,7 +224,7 @@ impl LogRotatorConfig {
         //! fn main() {
         //!     set_log_level(LogLevel::File);
         //!     set_log_path(LogConfig::Rotator(LogRotatorConfig::new(
-        //!         PathBuf::from("C:/Users/qruie/Documents"), // Logging directory
+        //!         std::env::temp_dir(), // Logging directory
         //!         5 * 1024 * 1024, // 5MB
         //!         Duration::from_secs(2), // Duration for log splits
         //!     )));
@@ -74,8 +234,200 @@ impl LogRotatorConfig {
             log_path,
             max_size,
             max_time,
+            max_files: None,
+            max_age: None,
+            max_total_size: None,
+            compression: Compression::None,
+            schedule: None,
+        }
+    }
+
+    pub fn with_schedule(mut self, schedule: RotationSchedule) -> Self {
+        //! Rotates the log file on wall-clock boundaries (see `RotationSchedule`), in addition to `max_size` and `max_time`.
+        //! ```rust
+        //! use logger_rust::*;
+        //! use std::time::Duration;
+        //! use std::path::PathBuf;
+        //!
+        //! // Rotate every day at midnight, whatever the size; never rotate because of `max_time`
+        //! let config = LogRotatorConfig::new(PathBuf::from("logs"), u64::MAX, Duration::MAX)
+        //!     .with_schedule(RotationSchedule::Daily { hour: 0, minute: 0 });
+        //! assert!(config.schedule.is_some());
+        //! ```
+        self.schedule = Some(schedule);
+        self
+    }
+
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        //! Keeps at most `max_files` rotated files, the oldest ones are deleted first.
+        //! ```rust
+        //! use logger_rust::*;
+        //! use std::time::Duration;
+        //! use std::path::PathBuf;
+        //!
+        //! let config = LogRotatorConfig::new(PathBuf::from("logs"), 5 * 1024 * 1024, Duration::from_secs(3600))
+        //!     .with_max_files(10)
+        //!     .with_max_age(Duration::from_secs(7 * 24 * 3600))
+        //!     .with_max_total_size(100 * 1024 * 1024);
+        //! assert_eq!(config.max_files, Some(10));
+        //! ```
+        //! Only the rotated files of the active log file are deleted, other files in the log directory are left alone:
+        //! ```rust
+        //! use logger_rust::*;
+        //! use std::{fs, time::Duration};
+        //!
+        //! let dir = std::env::temp_dir().join(format!("logger-rust-retention-{}", std::process::id()));
+        //! fs::create_dir_all(&dir).unwrap();
+        //! for foreign in ["app_settings.toml", "app_backup.log", "2020-01-01@00-00-00.log"] {
+        //!     fs::write(dir.join(foreign), "not a log").unwrap();
+        //! }
+        //!
+        //! set_log_level(LogLevel::File);
+        //! set_log_file_name(LogFileName::Fixed("app.log".to_string()));
+        //! // Rotate before every record, and keep a single rotated file
+        //! set_log_path(LogConfig::Rotator(
+        //!     LogRotatorConfig::new(dir.clone(), 0, Duration::MAX).with_max_files(1),
+        //! ));
+        //! for i in 0..4 {
+        //!     log_info!("Record {}", i);
+        //! }
+        //!
+        //! for foreign in ["app_settings.toml", "app_backup.log", "2020-01-01@00-00-00.log"] {
+        //!     assert!(dir.join(foreign).exists());
+        //! }
+        //! let rotated = fs::read_dir(&dir).unwrap()
+        //!     .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with("app_rot-"))
+        //!     .count();
+        //! assert_eq!(rotated, 1);
+        //! # set_log_level(LogLevel::Console);
+        //! # fs::remove_dir_all(&dir).unwrap();
+        //! ```
+        self.max_files = Some(max_files);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        //! Deletes rotated files that were last modified more than `max_age` ago.
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_max_total_size(mut self, max_total_size: u64) -> Self {
+        //! Deletes the oldest rotated files while the active and rotated files together take more than `max_total_size` bytes.
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        //! Compresses rotated files in a background thread (see `Compression`).
+        //! ```rust
+        //! use logger_rust::*;
+        //! use std::time::Duration;
+        //! use std::path::PathBuf;
+        //!
+        //! let config = LogRotatorConfig::new(PathBuf::from("logs"), 5 * 1024 * 1024, Duration::from_secs(3600))
+        //!     .with_compression(Compression::Gzip) // requires the `gzip` feature
+        //!     .with_max_files(10);
+        //! assert_eq!(config.compression, Compression::Gzip);
+        //! ```
+        self.compression = compression;
+        self
+    }
+
+    pub(crate) fn apply_retention(&self, active: &Path) -> io::Result<()> {
+        //! Deletes the rotated files of the `active` log file that exceed the retention settings.
+        if self.max_files.is_none() && self.max_age.is_none() && self.max_total_size.is_none() {
+            return Ok(());
+        }
+        let mut files = rotated_files(active)?;
+        // Oldest first
+        files.sort_by_key(|(_, modified, _)| *modified);
+
+        let mut keep = Vec::with_capacity(files.len());
+        for (path, modified, size) in files {
+            let expired = self.max_age.is_some_and(|max_age| modified.elapsed().unwrap_or_default() > max_age);
+            if expired {
+                fs::remove_file(&path)?;
+            } else {
+                keep.push((path, size));
+            }
+        }
+        if let Some(max_files) = self.max_files {
+            while keep.len() > max_files {
+                let (path, _) = keep.remove(0);
+                fs::remove_file(&path)?;
+            }
+        }
+        if let Some(max_total_size) = self.max_total_size {
+            let active_size = fs::metadata(active).map(|metadata| metadata.len()).unwrap_or(0);
+            let mut total = active_size + keep.iter().map(|(_, size)| size).sum::<u64>();
+            while total > max_total_size && !keep.is_empty() {
+                let (path, size) = keep.remove(0);
+                fs::remove_file(&path)?;
+                total -= size;
+            }
         }
+        Ok(())
+    }
+}
+
+fn rotated_files(active: &Path) -> io::Result<Vec<(PathBuf, SystemTime, u64)>> {
+    //! Lists the rotated files of the `active` log file, i.e. the files named like the rotation names them
+    //! (`<stem>_rot-N.<ext>` or `<stem>_<period label>[-N].<ext>`, optionally compressed, e.g. `app_rot-2.log.gz`).
+    //! Any other file in the directory, including the active files of other processes, is left alone.
+    let dir = match active.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let stem = active.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let extension = active.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_else(|| "log".to_string());
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_rotated_name(&name, &stem, &extension) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((entry.path(), metadata.modified()?, metadata.len()));
+        }
+    }
+    Ok(files)
+}
+
+fn is_rotated_name(name: &str, stem: &str, extension: &str) -> bool {
+    //! Checks whether `name` is a rotated name of the `<stem>.<extension>` log file, see `rotated_files`.
+    let name = Compression::EXTENSIONS
+        .iter()
+        .find_map(|compressed| name.strip_suffix(compressed).and_then(|name| name.strip_suffix('.')))
+        .unwrap_or(name);
+    let Some(suffix) = name
+        .strip_suffix(extension)
+        .and_then(|name| name.strip_suffix('.'))
+        .and_then(|name| name.strip_prefix(stem))
+        .and_then(|name| name.strip_prefix('_'))
+    else {
+        return false;
+    };
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if let Some(n) = suffix.strip_prefix("rot-") {
+        return is_number(n);
     }
+    // Period labels, see `RotationSchedule::label`: `d` stands for a digit
+    ["dddd-dd-ddTdd-dd", "dddd-dd-ddTdd", "dddd-dd-dd", "dddd-Wdd"].iter().any(|pattern| {
+        suffix.len() >= pattern.len()
+            && suffix.is_char_boundary(pattern.len())
+            && pattern.bytes().zip(suffix.bytes()).all(|(p, b)| match p {
+                b'd' => b.is_ascii_digit(),
+                p => p == b,
+            })
+            && {
+                let rest = &suffix[pattern.len()..];
+                rest.is_empty() || rest.strip_prefix('-').is_some_and(is_number)
+            }
+    })
 }
 
 impl From<&str> for LogPath {
@@ -87,8 +439,9 @@ impl From<&str> for LogPath {
 /// use logger_rust::*;
 ///
 /// fn main() {
+///     let dir = std::env::temp_dir();
 ///     set_log_level(LogLevel::File);
-///     set_log_path(LogConfig::Path(LogPath::from("C:/Users/qruie/Documents")));
+///     set_log_path(LogConfig::Path(LogPath::from(dir.to_str().unwrap())));
 ///     // ..
 /// }
 /// ```
@@ -107,8 +460,9 @@ impl From<PathBuf> for LogPath {
 /// use std::path::PathBuf;
 ///
 /// fn main() {
+///     let dir: PathBuf = std::env::temp_dir();
 ///     set_log_level(LogLevel::File);
-///     set_log_path(LogConfig::Path(LogPath::from("C:/Users/qruie/Documents")));
+///     set_log_path(LogConfig::Path(LogPath::from(dir)));
 ///     // ..
 /// }
 /// ```
@@ -0,0 +1,143 @@
+//! # Record
+//! A single log record as it is passed from `log_message` to the sinks.
+//! Sinks receive the raw pieces (level, timestamp, message, structured fields) and decide by themselves how to render them.
+use std::{fmt, thread};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// The level string of the record (e.g. `"ERROR"`, `"INFO"`).
+    pub level: String,
+    /// The timestamp of the record, as produced by `current_time`.
+    pub now: String,
+    /// The already formatted message.
+    pub message: String,
+    /// The target of the record (e.g. the `target` of a `log` crate record), if any.
+    pub target: Option<String>,
+    /// The module path the record was emitted from, if known.
+    pub module_path: Option<String>,
+    /// The source file the record was emitted from, if known.
+    pub file: Option<String>,
+    /// The source line the record was emitted from, if known.
+    pub line: Option<u32>,
+    /// The name (or id, for unnamed threads) of the thread the record was created on.
+    pub thread: String,
+    /// Structured key/value fields attached to the record (e.g. by `log_info!(user_id = 42; "...")`).
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    /// Creates a new record from the same three values that `log_message` takes.
+    ///
+    /// ```
+    /// use logger_rust::*;
+    ///
+    /// let record = Record::new("INFO", &current_time(), "Something happened");
+    /// assert_eq!(record.level, "INFO");
+    /// ```
+    pub fn new(level: &str, now: &str, message: &str) -> Self {
+        Self {
+            level: level.to_string(),
+            now: now.to_string(),
+            message: message.to_string(),
+            target: None,
+            module_path: None,
+            file: None,
+            line: None,
+            thread: current_thread(),
+            fields: Vec::new(),
+        }
+    }
+}
+
+/// Returns the name of the current thread, falling back to its id for unnamed threads.
+fn current_thread() -> String {
+    let current = thread::current();
+    match current.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", current.id()),
+    }
+}
+
+/// A typed value of a structured field.
+///
+/// ```
+/// use logger_rust::*;
+///
+/// assert_eq!(Value::from(42), Value::Int(42));
+/// assert_eq!(Value::from("alice").to_string(), "alice");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A string value.
+    Str(String),
+    /// A signed integer.
+    Int(i64),
+    /// An unsigned integer.
+    UInt(u64),
+    /// A floating point number.
+    Float(f64),
+    /// A boolean.
+    Bool(bool),
+    /// A value captured with the `%` sigil, rendered through its `Display` implementation.
+    Display(String),
+    /// A value captured with the `?` sigil, rendered through its `Debug` implementation.
+    Debug(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) | Value::Display(s) | Value::Debug(s) => f.write_str(s),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::UInt(u) => write!(f, "{}", u),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+macro_rules! impl_value_from {
+    ($variant:ident($target:ty): $($source:ty),*) => {
+        $(
+            impl From<$source> for Value {
+                fn from(value: $source) -> Self {
+                    Value::$variant(value as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_value_from!(Int(i64): i8, i16, i32, i64, isize);
+impl_value_from!(UInt(u64): u8, u16, u32, u64, usize);
+impl_value_from!(Float(f64): f32, f64);
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+impl From<&String> for Value {
+    fn from(value: &String) -> Self {
+        Value::Str(value.clone())
+    }
+}
@@ -1,18 +1,25 @@
-pub use crate::config::{LogVariables, LogVariablesImpl, LogLevel, LOG_ROTATOR_CONFIG, LOG_MUTEX};
+pub use crate::config::{LogVariables, LogVariablesImpl, LogLevel, FlushPolicy, LogFileName, LOG_ROTATOR_CONFIG, LOG_MUTEX, FLUSH_POLICY, LOG_FILE_NAME};
 pub use crate::{log_error, LOG_PATH};
 pub use crate::log_rotator::{
     LogPath,
     LogConfig,
     LogRotatorConfig,
+    Compression,
+    RotationSchedule,
+    Weekday,
 
 };
+use crate::error::{lock, report_error, try_lock, LoggerError};
 use std::{
-    path::PathBuf,
-    process,
+    fs,
+    path::Path,
     thread,
-    time::Duration,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
+/// Whether the background thread flushing the log file for `FlushPolicy::Interval` is running.
+static FLUSHER_RUNNING: AtomicBool = AtomicBool::new(false);
+
 pub fn set_log_level(level: LogLevel) {
 //! Sets the log level to the provided value.
 //!
@@ -28,10 +35,142 @@ pub fn set_log_level(level: LogLevel) {
 //! ```
 //! 
     let log_variables = LogVariablesImpl;
-    let mut log_level = log_variables.log_level().lock().unwrap();
+    let mut log_level = lock(log_variables.log_level());
     *log_level = level;
 }
 
+pub fn try_set_log_level(level: LogLevel) -> Result<(), LoggerError> {
+//! Same as `set_log_level`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+    *try_lock(LogVariablesImpl.log_level(), "log level")? = level;
+    Ok(())
+}
+
+pub fn set_log_file_name(name: LogFileName) {
+//! Sets the name of the active log file (see `LogFileName`).
+//!
+//! # Examples
+//!
+//! ```
+//! use logger_rust::*;
+//!
+//! // Always write to `app.log`, rotated files become `app_rot-1.log`, `app_rot-2.log`, ...
+//! set_log_file_name(LogFileName::Fixed("app.log".to_string()));
+//! # set_log_file_name(LogFileName::PerProcess);
+//! ```
+    *lock(&LOG_FILE_NAME) = name;
+}
+
+pub fn try_set_log_file_name(name: LogFileName) -> Result<(), LoggerError> {
+//! Same as `set_log_file_name`, but checks the name first:
+//! a `LogFileName::Fixed` name must be a plain file name, not empty and without path separators
+//! (the directory is set with `set_log_path`).
+//!
+//! # Examples
+//!
+//! ```
+//! use logger_rust::*;
+//!
+//! assert!(matches!(
+//!     try_set_log_file_name(LogFileName::Fixed("logs/app.log".to_string())),
+//!     Err(LoggerError::InvalidConfig(_))
+//! ));
+//! ```
+    validate_log_file_name(&name)?;
+    *try_lock(&LOG_FILE_NAME, "log file name")? = name;
+    Ok(())
+}
+
+/// Checks that a `LogFileName::Fixed` name is a plain file name.
+pub(crate) fn validate_log_file_name(name: &LogFileName) -> Result<(), LoggerError> {
+    if let LogFileName::Fixed(file_name) = name {
+        if file_name.is_empty()
+            || file_name == "."
+            || file_name == ".."
+            || file_name.contains(std::path::is_separator)
+        {
+            return Err(LoggerError::InvalidConfig(format!("`{}` is not a valid log file name", file_name)));
+        }
+    }
+    Ok(())
+}
+
+pub fn set_flush_policy(policy: FlushPolicy) {
+//! Sets when the buffered log file is flushed (see `FlushPolicy`).
+//! A zero `FlushPolicy::Interval` is the same as `FlushPolicy::EveryRecord`.
+//!
+//! # Examples
+//!
+//! ```
+//! use logger_rust::*;
+//! use std::time::Duration;
+//!
+//! // Flush the buffered lines at most every 500ms
+//! set_flush_policy(FlushPolicy::Interval(Duration::from_millis(500)));
+//! log_info!("Buffered");
+//!
+//! // Before exiting, or the buffered lines are lost
+//! flush_sinks();
+//! # set_flush_policy(FlushPolicy::EveryRecord);
+//! ```
+    let policy = match policy {
+        FlushPolicy::Interval(interval) if interval.is_zero() => FlushPolicy::EveryRecord,
+        policy => policy,
+    };
+    *lock(&FLUSH_POLICY) = policy;
+    start_flusher(policy).unwrap_or_else(report_error);
+}
+
+pub fn try_set_flush_policy(policy: FlushPolicy) -> Result<(), LoggerError> {
+//! Same as `set_flush_policy`, but returns the errors instead of reporting them.
+//! Also fails with `LoggerError::InvalidConfig` for a zero `FlushPolicy::Interval` (which `set_flush_policy`
+//! treats as `FlushPolicy::EveryRecord`), and with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+    if let FlushPolicy::Interval(interval) = policy {
+        if interval.is_zero() {
+            return Err(LoggerError::InvalidConfig("the flush interval must not be zero".to_string()));
+        }
+    }
+    *try_lock(&FLUSH_POLICY, "flush policy")? = policy;
+    start_flusher(policy)
+}
+
+/// Starts the flusher thread for `FlushPolicy::Interval`, unless it is already running.
+fn start_flusher(policy: FlushPolicy) -> Result<(), LoggerError> {
+    if let FlushPolicy::Interval(_) = policy {
+        if !FLUSHER_RUNNING.swap(true, Ordering::SeqCst) {
+            thread::Builder::new()
+                .name("logger-rust-flusher".to_string())
+                .spawn(run_flusher)
+                .map_err(|e| {
+                    FLUSHER_RUNNING.store(false, Ordering::SeqCst);
+                    LoggerError::Spawn(e)
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// Periodically flushes the log file while the flush policy is `FlushPolicy::Interval`.
+fn run_flusher() {
+    loop {
+        let interval = match *lock(&FLUSH_POLICY) {
+            FlushPolicy::Interval(interval) => interval,
+            _ => {
+                FLUSHER_RUNNING.store(false, Ordering::SeqCst);
+                // The policy may have been switched back to `Interval` right before the flag was cleared
+                if matches!(*lock(&FLUSH_POLICY), FlushPolicy::Interval(_))
+                    && !FLUSHER_RUNNING.swap(true, Ordering::SeqCst)
+                {
+                    continue;
+                }
+                return;
+            }
+        };
+        thread::sleep(interval);
+        crate::log_file::flush_file()
+            .unwrap_or_else(|e| report_error(LoggerError::Sink("flush the log file".to_string(), e)));
+    }
+}
+
 pub fn set_log_path(config: LogConfig) {
     //! Sets the log path and, optionally, the log rotator configuration.
     //!
@@ -53,7 +192,7 @@ pub fn set_log_path(config: LogConfig) {
     //! use std::path::PathBuf;
     //!
     //! set_log_level(LogLevel::File);
-    //! set_log_path(LogConfig::Path(LogPath::from("C:/Users/qruie/Documents")));
+    //! set_log_path(LogConfig::Path(LogPath::from(std::env::temp_dir())));
     //! ```
     //! 
     //! ### Set the log path with a log rotator
@@ -64,59 +203,130 @@ pub fn set_log_path(config: LogConfig) {
     //! 
     //! set_log_level(LogLevel::File);
     //! set_log_path(LogConfig::Rotator(LogRotatorConfig::new(
-    //!     PathBuf::from("C:/Users/qruie/Documents"),
+    //!     std::env::temp_dir(),
     //!     5 * 1024 * 1024,
     //!     Duration::from_secs(2),
     //! )));
     //! ```
-    let log_variables = LogVariablesImpl;
-    let log_level = log_variables.log_level().lock().unwrap();
-    if *log_level != LogLevel::File && *log_level != LogLevel::Both {
+    //!
+    //! An invalid path is reported with `log_error!` and the previous path is kept.
+    //! Use `try_set_log_path` to handle the error yourself.
+    let log_level = *lock(LogVariablesImpl.log_level());
+    if log_level != LogLevel::File && log_level != LogLevel::Both {
         panic!("
         Cannot call set_log_path when log level is no set to `LogLevel::File` or `LogLevel::Both`
         Please, specify the `LogLevel::Console` or `LogLevel::Path` if you want to use `set_log_path`
         `set_log_level(LogLevel::File);` // OR `LogLevel::Both` <──────────────────────────────┘
         ---------------------------------------\n");
     }
-    let handle = thread::spawn(move || {
-        let path = match config {
-            LogConfig::Path(LogPath::Path(path)) => path,
-            LogConfig::Rotator(rotator_config) => {
-                let log_path = rotator_config.log_path.clone();
-                let mut log_rotator_config = LOG_ROTATOR_CONFIG.lock().unwrap();
-                *log_rotator_config = Some(rotator_config);
-                log_path
-            }
-        };
-        match (
-            path.exists(),
-            path.is_dir(),
-            path.metadata().map(|m| m.permissions().readonly()),
-        ) {
-            (false, _, _) => {
-                log_error!("Path is not correct: {}", path.display());
-                thread::sleep(Duration::from_secs(10));
-                process::exit(1);
-            }
-            (_, false, _) => {
-                log_error!("Path is not a directory: {}", path.display());
-                thread::sleep(Duration::from_secs(10));
-                process::exit(1);
-            }
-            (_, _, Err(e)) => {
-                log_error!("Failed to get metadata for path {}: {}", path.display(), e);
-                thread::sleep(Duration::from_secs(10));
-                process::exit(1);
-            }
-            (_, _, Ok(false)) => {
-                log_error!("Not enough permissions to access the path: '{}'", path.display());
-                thread::sleep(Duration::from_secs(10));
-                process::exit(1);
-            }
-            _ => {}
+    if let Err(e) = try_set_log_path(config) {
+        log_error!("{}", e);
+    }
+}
+
+pub fn try_set_log_path(config: LogConfig) -> Result<(), LoggerError> {
+    //! Fallible version of `set_log_path`.
+    //!
+    //! The path is validated synchronously: it must be an existing, writable directory,
+    //! the log level must be `LogLevel::File` or `LogLevel::Both`, and the `Compression` of a rotator must have its cargo feature enabled.
+    //! On error, nothing is changed and the error is returned to the caller.
+    //!
+    //! # Examples
+    //! ```
+    //! use logger_rust::*;
+    //!
+    //! set_log_level(LogLevel::File);
+    //! match try_set_log_path(LogConfig::Path(LogPath::from("/definitely/not/here"))) {
+    //!     Err(LoggerError::PathNotFound(path)) => eprintln!("No such directory: {}", path.display()),
+    //!     Err(e) => eprintln!("{}", e),
+    //!     Ok(()) => {}
+    //! }
+    //! # set_log_level(LogLevel::Console);
+    //! ```
+    let log_level = *try_lock(LogVariablesImpl.log_level(), "log level")?;
+    if log_level != LogLevel::File && log_level != LogLevel::Both {
+        return Err(invalid_log_level());
+    }
+    let path = match &config {
+        LogConfig::Path(LogPath::Path(path)) => path.clone(),
+        LogConfig::Rotator(rotator_config) => rotator_config.log_path.clone(),
+    };
+    validate_log_dir(&path)?;
+    if let LogConfig::Rotator(rotator_config) = &config {
+        if let Some(feature) = rotator_config.compression.missing_feature() {
+            return Err(LoggerError::InvalidConfig(format!(
+                "{:?} compression requires the `{}` cargo feature",
+                rotator_config.compression, feature
+            )));
         }
-        let mut log_path = LOG_PATH.lock().unwrap();
-        *log_path = PathBuf::from(path);
-    });
-    handle.join().unwrap();
-}
\ No newline at end of file
+    }
+    if let LogConfig::Rotator(rotator_config) = config {
+        *try_lock(&LOG_ROTATOR_CONFIG, "log rotator configuration")? = Some(rotator_config);
+    }
+    *try_lock(&LOG_PATH, "log path")? = path;
+    Ok(())
+}
+
+pub fn try_set_log_path_or_create(config: LogConfig) -> Result<(), LoggerError> {
+    //! Same as `try_set_log_path`, but creates the log directory (and its parents) if it does not exist yet.
+    //! The log level is checked first, so nothing is created when the path could not be set anyway.
+    //!
+    //! # Examples
+    //! ```
+    //! use logger_rust::*;
+    //!
+    //! let dir = std::env::temp_dir().join("logger-rust-doc").join("nested");
+    //! // Nothing is created while the output is `LogLevel::Console`
+    //! let unused = std::env::temp_dir().join(format!("logger-rust-doc-{}", std::process::id()));
+    //! assert!(try_set_log_path_or_create(LogConfig::Path(LogPath::from(unused.clone()))).is_err());
+    //! assert!(!unused.exists());
+    //!
+    //! set_log_level(LogLevel::File);
+    //! try_set_log_path_or_create(LogConfig::Path(LogPath::from(dir.clone()))).unwrap();
+    //! assert!(dir.is_dir());
+    //! # set_log_level(LogLevel::Console);
+    //! ```
+    // Fail before touching the file system if the path cannot be set anyway
+    let log_level = *try_lock(LogVariablesImpl.log_level(), "log level")?;
+    if log_level != LogLevel::File && log_level != LogLevel::Both {
+        return Err(invalid_log_level());
+    }
+    let path = match &config {
+        LogConfig::Path(LogPath::Path(path)) => path,
+        LogConfig::Rotator(rotator_config) => &rotator_config.log_path,
+    };
+    if !path.exists() {
+        fs::create_dir_all(path).map_err(|e| LoggerError::Io(path.clone(), e))?;
+    }
+    try_set_log_path(config)
+}
+
+fn invalid_log_level() -> LoggerError {
+    LoggerError::InvalidConfig(
+        "the log path can only be set when the log level is `LogLevel::File` or `LogLevel::Both`".to_string(),
+    )
+}
+
+/// Checks that `path` is an existing directory this process can create files in.
+pub(crate) fn validate_log_dir(path: &Path) -> Result<(), LoggerError> {
+    if !path.exists() {
+        return Err(LoggerError::PathNotFound(path.to_path_buf()));
+    }
+    let metadata = path.metadata().map_err(|e| LoggerError::Io(path.to_path_buf(), e))?;
+    if !metadata.is_dir() {
+        return Err(LoggerError::NotADirectory(path.to_path_buf()));
+    }
+    // The permission bits do not tell whether this process may write there, so try it
+    static PROBES: AtomicUsize = AtomicUsize::new(0);
+    let probe = path.join(format!(
+        ".logger-rust-probe-{}-{}",
+        std::process::id(),
+        PROBES.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|_| LoggerError::PermissionDenied(path.to_path_buf()))
+}
@@ -0,0 +1,152 @@
+//! # Capturing records in tests
+//! `capture_logs` collects the records logged by the current thread while a closure runs,
+//! so that parallel tests do not see each other's records. `assert_logged!` checks that one of them matches:
+//! ```
+//! use logger_rust::*;
+//!
+//! fn reserve(free: u64) {
+//!     if free < 10 {
+//!         log_warn!(free = free; "disk is almost full");
+//!     }
+//! }
+//!
+//! let logs = capture_logs(|| {
+//!     reserve(3);
+//!     assert_logged!(Severity::Warn, contains = "almost full"); // records captured so far
+//! });
+//! assert_logged!(logs, Severity::Warn, contains = "almost full");
+//! assert_eq!(logs[0].fields[0].1, Value::UInt(3));
+//! ```
+//! Records are still written to the console, the file and the sinks; records logged by other threads
+//! (including threads spawned inside the closure) are not captured. To collect the records of every thread,
+//! register a `CaptureSink` with `add_sink` instead.
+use crate::error::lock;
+use crate::record::Record;
+use crate::sink::Sink;
+use std::{
+    cell::RefCell,
+    fmt,
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The number of `capture_logs` calls running on any thread, so that logging only looks at the thread-local
+/// captures when there is one.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The records captured by the `capture_logs` calls running on this thread, innermost last.
+    static CAPTURES: RefCell<Vec<Vec<Record>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A sink that keeps every record it receives in memory. Clones share the same records.
+///
+/// ```
+/// use logger_rust::*;
+///
+/// let capture = CaptureSink::new();
+/// add_sink(capture.clone());
+/// log_info!("stored in memory");
+/// assert_logged!(capture.records(), Severity::Info, contains = "in memory");
+/// # clear_sinks();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CaptureSink {
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+impl CaptureSink {
+    /// Creates an empty capture sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the records received so far.
+    pub fn records(&self) -> Vec<Record> {
+        lock(&self.records).clone()
+    }
+
+    /// Removes the records received so far.
+    pub fn clear(&self) {
+        lock(&self.records).clear();
+    }
+}
+
+impl Sink for CaptureSink {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        lock(&self.records).push(record.clone());
+        Ok(())
+    }
+}
+
+/// Runs `f` and returns the records the current thread logged meanwhile.
+pub fn capture_logs<F: FnOnce()>(f: F) -> Vec<Record> {
+    CAPTURES.with(|captures| captures.borrow_mut().push(Vec::new()));
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    // End the capture even if `f` panics (e.g. because of a failed assertion)
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    let records = CAPTURES.with(|captures| captures.borrow_mut().pop().unwrap_or_default());
+    if let Err(panic) = result {
+        panic::resume_unwind(panic);
+    }
+    records
+}
+
+/// Adds the record to the captures running on the current thread, if any.
+pub(crate) fn capture(record: &Record) {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    CAPTURES.with(|captures| {
+        for captured in captures.borrow_mut().iter_mut() {
+            captured.push(record.clone());
+        }
+    });
+}
+
+#[doc(hidden)]
+/// Used by `assert_logged!` without records: the records captured so far by the innermost `capture_logs` of this thread.
+pub fn __captured() -> Vec<Record> {
+    CAPTURES.with(|captures| match captures.borrow().last() {
+        Some(records) => records.clone(),
+        None => panic!("`assert_logged!` without records can only be used inside `capture_logs`"),
+    })
+}
+
+#[doc(hidden)]
+/// Used by `assert_logged!`: panics unless one of the records has the level and contains the text.
+pub fn __assert_logged(records: &[Record], level: &dyn fmt::Display, text: &str) {
+    let level = level.to_string();
+    if records
+        .iter()
+        .any(|record| record.level.eq_ignore_ascii_case(&level) && record.message.contains(text))
+    {
+        return;
+    }
+    let mut logged = String::new();
+    for record in records {
+        logged.push_str(&format!("\n    [{}] {}", record.level, record.message));
+    }
+    if logged.is_empty() {
+        logged.push_str(" none");
+    }
+    panic!("expected a {} record containing {:?}, captured records:{}", level, text, logged);
+}
+
+#[macro_export]
+/// ## Macro rules - assert_logged!
+/// Asserts that a record with the given level (a `Severity`, a `Level` or a level name) contains the given text:
+/// - `assert_logged!(records, level, contains = "...")` checks the records returned by `capture_logs`
+///   or `CaptureSink::records`;
+/// - `assert_logged!(level, contains = "...")` checks the records captured so far, inside `capture_logs`.
+macro_rules! assert_logged {
+    ($level:expr, contains = $text:expr $(,)?) => {
+        $crate::sink::capture::__assert_logged(&$crate::sink::capture::__captured(), &$level, $text)
+    };
+    ($records:expr, $level:expr, contains = $text:expr $(,)?) => {
+        $crate::sink::capture::__assert_logged(&$records, &$level, $text)
+    };
+}
@@ -0,0 +1,198 @@
+//! # Sinks
+//! A sink is a destination for log records. The crate ships two built-in sinks:
+//! - `ConsoleSink` prints records to stderr (used by `LogLevel::Console` and `LogLevel::Both`);
+//! - `FileSink` appends records to the log file (used by `LogLevel::File` and `LogLevel::Both`).
+//!
+//! Any number of additional sinks can be registered at runtime with `add_sink`.
+//! They receive every record that passes the severity threshold, regardless of the current `LogLevel`.
+//!
+//! ```
+//! use logger_rust::*;
+//! use std::io;
+//! use std::sync::Mutex;
+//!
+//! struct CountingSink(Mutex<usize>);
+//!
+//! impl Sink for CountingSink {
+//!     fn write(&self, _record: &Record) -> io::Result<()> {
+//!         *self.0.lock().unwrap() += 1;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! add_sink(CountingSink(Mutex::new(0)));
+//! log_info!("Goes to the console and to CountingSink");
+//! clear_sinks();
+//! ```
+pub mod capture;
+#[cfg(unix)]
+pub mod syslog;
+
+use crate::record::Record;
+use crate::config::{LogVariables, LogVariablesImpl, LogLevel};
+use crate::log_file::{write_line, flush_file};
+use crate::config::Severity;
+use crate::format::{console_format, file_format, Format};
+use crate::theme::colors_enabled;
+use crate::level::level_rank;
+use crate::error::{lock, read, report_error, try_write, write, LoggerError};
+use std::{
+    io::{self, Write},
+    sync::RwLock,
+};
+
+/// Creates a sink enabled by name in a configuration file.
+type SinkFactory = Box<dyn Fn() -> Box<dyn Sink> + Send + Sync>;
+
+lazy_static::lazy_static! {
+    /// Sinks registered at runtime via `add_sink`.
+    static ref SINKS: RwLock<Vec<Box<dyn Sink>>> = RwLock::new(Vec::new());
+    /// Sink types registered via `register_sink_type`, by name.
+    static ref SINK_TYPES: RwLock<Vec<(String, SinkFactory)>> = RwLock::new(Vec::new());
+}
+
+/// A destination for log records.
+pub trait Sink: Send + Sync {
+    /// Writes a single record to the destination.
+    fn write(&self, record: &Record) -> io::Result<()>;
+
+    /// Flushes any buffered records. Does nothing by default.
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Built-in sink that prints records to stderr, rendered with the `console_format`.
+/// `Format::Colored` falls back to `Format::Plain` when colours are disabled (see `ColorMode`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConsoleSink;
+
+impl Sink for ConsoleSink {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        let format = match console_format() {
+            Format::Colored if !colors_enabled() => Format::Plain,
+            format => format,
+        };
+        writeln!(io::stderr(), "{}", format.render(record))
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// Built-in sink that appends records to the log file configured by `set_log_path`, rendered with the `file_format`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSink;
+
+impl Sink for FileSink {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        // Custom levels flush like the built-in level of the same rank; rank 0 is above ERROR
+        let severity = level_rank(&record.level).map(|rank| Severity::from_rank(rank.max(1)));
+        write_line(&file_format().render(record), severity)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        flush_file()
+    }
+}
+
+/// Registers an additional sink. Registered sinks receive every emitted record.
+pub fn add_sink<S: Sink + 'static>(sink: S) {
+    write(&SINKS).push(Box::new(sink));
+}
+
+/// Same as `add_sink`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_add_sink<S: Sink + 'static>(sink: S) -> Result<(), LoggerError> {
+    try_write(&SINKS, "sinks")?.push(Box::new(sink));
+    Ok(())
+}
+
+/// Removes all sinks registered with `add_sink`. The built-in console and file sinks are not affected.
+pub fn clear_sinks() {
+    write(&SINKS).clear();
+}
+
+/// Same as `clear_sinks`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_clear_sinks() -> Result<(), LoggerError> {
+    try_write(&SINKS, "sinks")?.clear();
+    Ok(())
+}
+
+/// Registers a sink type under `name`, so that configuration files can enable it in their `[sinks]` section
+/// (see `load_config`). Registering the same name again replaces the factory.
+///
+/// ```
+/// use logger_rust::*;
+/// use std::io;
+///
+/// struct AuditSink;
+///
+/// impl Sink for AuditSink {
+///     fn write(&self, _record: &Record) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// register_sink_type("audit", || Box::new(AuditSink));
+/// load_config_str("[sinks]\naudit = true").unwrap();
+/// # clear_sinks();
+/// ```
+pub fn register_sink_type<F: Fn() -> Box<dyn Sink> + Send + Sync + 'static>(name: &str, factory: F) {
+    let mut sink_types = write(&SINK_TYPES);
+    sink_types.retain(|(registered, _)| registered != name);
+    sink_types.push((name.to_string(), Box::new(factory)));
+}
+
+/// Creates a sink of the type registered under `name`, if any.
+pub(crate) fn new_sink(name: &str) -> Option<Box<dyn Sink>> {
+    read(&SINK_TYPES)
+        .iter()
+        .find(|(registered, _)| registered == name)
+        .map(|(_, factory)| factory())
+}
+
+/// Replaces the registered sinks with the given ones.
+pub(crate) fn replace_sinks(sinks: Vec<Box<dyn Sink>>) -> Result<(), LoggerError> {
+    *try_write(&SINKS, "sinks")? = sinks;
+    Ok(())
+}
+
+/// Flushes the built-in sinks selected by the current `LogLevel` and every registered sink.
+pub fn flush_sinks() {
+    let log_level = *lock(LogVariablesImpl.log_level());
+    if log_level != LogLevel::File {
+        check("flush the console", ConsoleSink.flush());
+    }
+    if log_level != LogLevel::Console {
+        check("flush the log file", FileSink.flush());
+    }
+    // Reported once the lock is released, so that the error handler can log
+    let errors: Vec<io::Error> = read(&SINKS).iter().filter_map(|sink| sink.flush().err()).collect();
+    for e in errors {
+        check("flush a sink", Err(e));
+    }
+}
+
+/// Passes the record to the built-in sinks selected by the current `LogLevel` and to every registered sink.
+pub(crate) fn dispatch(record: &Record) {
+    let log_level = *lock(LogVariablesImpl.log_level());
+    if log_level != LogLevel::File {
+        check("write to the console", ConsoleSink.write(record));
+    }
+    if log_level != LogLevel::Console {
+        check("write to the log file", FileSink.write(record));
+    }
+    // Reported once the lock is released, so that the error handler can log
+    let errors: Vec<io::Error> = read(&SINKS).iter().filter_map(|sink| sink.write(record).err()).collect();
+    for e in errors {
+        check("write to a sink", Err(e));
+    }
+}
+
+/// Passes a failed sink operation to the error handler.
+fn check(action: &str, result: io::Result<()>) {
+    if let Err(e) = result {
+        report_error(LoggerError::Sink(action.to_string(), e));
+    }
+}
@@ -0,0 +1,209 @@
+//! # Syslog
+//! `SyslogSink` sends records to the local syslog daemon through its Unix datagram socket (`/dev/log` by default),
+//! framed as RFC 5424 (the default) or as legacy RFC 3164 messages:
+//! ```text
+//! <14>1 2023-06-09T15:06:46.123456+02:00 - my_app 4242 - - Disk is almost full free=3
+//! <14>Jun  9 15:06:46 my_app[4242]: Disk is almost full free=3
+//! ```
+//! The level is mapped to a syslog severity (`ERROR` to `err`, `WARN` to `warning`, `INFO` to `info`,
+//! `DEBUG` and `TRACE` to `debug`; custom levels by their rank, rank `0` to `crit`), and structured fields
+//! are appended to the message as `key=value` suffixes. The socket is connected on the first record,
+//! and again after a failed send, so the sink survives a restart of the daemon.
+//! ```
+//! use logger_rust::*;
+//! use std::os::unix::net::UnixDatagram;
+//!
+//! let path = std::env::temp_dir().join(format!("logger-rust-syslog-{}.sock", std::process::id()));
+//! let listener = UnixDatagram::bind(&path).unwrap();
+//!
+//! let sink = SyslogSink::with_path(&path)
+//!     .with_facility(Facility::Local0)
+//!     .with_app_name("my_app");
+//! sink.write(&Record::new("WARN", &current_time(), "Disk is almost full")).unwrap();
+//!
+//! let mut buf = [0; 1024];
+//! let len = listener.recv(&mut buf).unwrap();
+//! let message = String::from_utf8_lossy(&buf[..len]);
+//! assert!(message.starts_with("<132>1 ")); // local0 (16) * 8 + warning (4)
+//! assert!(message.ends_with(&format!(" my_app {} - - Disk is almost full", std::process::id())));
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use crate::error::lock;
+use crate::format::strip_ansi;
+use crate::level::level_rank;
+use crate::record::Record;
+use crate::sink::Sink;
+use chrono::{Local, SecondsFormat};
+use std::{
+    fmt::Write as _,
+    io,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+/// The socket of the local syslog daemon on most Unix systems.
+pub const DEFAULT_SYSLOG_PATH: &str = "/dev/log";
+
+/// The syslog facility of the records, i.e. the kind of program that logs them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Facility {
+    Kern = 0,
+    /// Generic user-level messages (the default).
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// The framing of the messages sent to the daemon.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum SyslogFormat {
+    /// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`, with a nil hostname,
+    /// message id and structured data (the default).
+    #[default]
+    Rfc5424,
+    /// `<PRI>Mmm dd hh:mm:ss APP-NAME[PID]: MSG`, as sent by the libc `syslog` function.
+    Rfc3164,
+}
+
+/// Sink that sends records to the local syslog daemon.
+#[derive(Debug)]
+pub struct SyslogSink {
+    path: PathBuf,
+    facility: Facility,
+    app_name: String,
+    format: SyslogFormat,
+    socket: Mutex<Option<UnixDatagram>>,
+}
+
+impl Default for SyslogSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyslogSink {
+    /// Creates a sink for `/dev/log`, with the `User` facility, RFC 5424 framing
+    /// and the name of the executable as app-name.
+    pub fn new() -> Self {
+        Self::with_path(DEFAULT_SYSLOG_PATH)
+    }
+
+    /// Creates a sink for the socket at `path` (e.g. `/var/run/syslog` on macOS, or a local listener in tests).
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            facility: Facility::default(),
+            app_name: default_app_name(),
+            format: SyslogFormat::default(),
+            socket: Mutex::new(None),
+        }
+    }
+
+    /// Sets the facility of the records.
+    pub fn with_facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Sets the app-name (the tag, in RFC 3164) of the records. Whitespace is replaced with `_`.
+    pub fn with_app_name(mut self, app_name: &str) -> Self {
+        self.app_name = app_name.replace(char::is_whitespace, "_");
+        self
+    }
+
+    /// Sets the framing of the messages.
+    pub fn with_format(mut self, format: SyslogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Renders the record as a syslog message.
+    fn frame(&self, record: &Record) -> String {
+        let priority = self.facility as u8 * 8 + syslog_severity(&record.level);
+        let mut message = strip_ansi(&record.message);
+        for (key, value) in &record.fields {
+            let _ = write!(message, " {}={}", key, value);
+        }
+        let now = Local::now();
+        match self.format {
+            SyslogFormat::Rfc5424 => {
+                // APP-NAME is at most 48 printable ASCII characters, or the nil value
+                let app_name: String = self.app_name.chars().filter(char::is_ascii_graphic).take(48).collect();
+                format!(
+                    "<{}>1 {} - {} {} - - {}",
+                    priority,
+                    now.to_rfc3339_opts(SecondsFormat::Micros, false),
+                    if app_name.is_empty() { "-" } else { &app_name },
+                    process::id(),
+                    message
+                )
+            }
+            SyslogFormat::Rfc3164 => format!(
+                "<{}>{} {}[{}]: {}",
+                priority,
+                now.format("%b %e %H:%M:%S"),
+                self.app_name,
+                process::id(),
+                message
+            ),
+        }
+    }
+}
+
+impl Sink for SyslogSink {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        let message = self.frame(record);
+        let mut socket = lock(&self.socket);
+        // Taken out of the slot, so that a failed send reconnects on the next record (e.g. after a restart of the daemon)
+        let connected = match socket.take() {
+            Some(connected) => connected,
+            None => {
+                let connected = UnixDatagram::unbound()?;
+                connected.connect(&self.path)?;
+                connected
+            }
+        };
+        connected.send(message.as_bytes())?;
+        *socket = Some(connected);
+        Ok(())
+    }
+}
+
+/// Maps a built-in or custom level to a syslog severity, by rank. Unknown levels are `notice`.
+fn syslog_severity(level: &str) -> u8 {
+    match level_rank(level) {
+        Some(0) => 2, // crit
+        Some(1) => 3, // err
+        Some(2) => 4, // warning
+        Some(3) => 6, // info
+        Some(_) => 7, // debug
+        None => 5,    // notice
+    }
+}
+
+/// The file name of the executable, or `logger-rust` if it is unknown.
+fn default_app_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().replace(char::is_whitespace, "_")))
+        .unwrap_or_else(|| "logger-rust".to_string())
+}
@@ -0,0 +1,195 @@
+//! # Themes and colour detection
+//! The `Colored` format and the `log_trace!` output take their ANSI colours from the current `Theme`.
+//! Every colour is a raw escape sequence (e.g. `"\x1b[32m"` for green, `"\x1b[1m\x1b[31m"` for bold red);
+//! an empty string leaves the segment uncoloured.
+//!
+//! Whether the console gets colours at all is decided by the `ColorMode`. In `ColorMode::Auto` (the default),
+//! colours are used when stderr is a terminal, unless the `NO_COLOR` environment variable is set;
+//! `CLICOLOR_FORCE` (set to anything but `0`) forces them, even when stderr is piped (e.g. into `journalctl` or a file).
+//! Without colours, the console falls back from `Format::Colored` to `Format::Plain`. The file output is not affected.
+//! ```
+//! use logger_rust::*;
+//!
+//! set_theme(
+//!     Theme::default()
+//!         .with_level("INFO", "\x1b[32m")
+//!         .with_level("AUDIT", "\x1b[1m\x1b[35m")
+//!         .with_timestamp("\x1b[2m"),
+//! );
+//! set_color_mode(ColorMode::Always);
+//! log_info!("Green, with a dimmed timestamp");
+//! # set_theme(Theme::default());
+//! # set_color_mode(ColorMode::Auto);
+//! ```
+use crate::config::Severity;
+use crate::error::{read, try_write, write, LoggerError};
+use crate::level::level_color;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    sync::RwLock,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+lazy_static::lazy_static! {
+    static ref THEME: RwLock<Theme> = RwLock::new(Theme::default());
+    /// Whether colours are used in `ColorMode::Auto`, checked once.
+    static ref AUTO_COLORS: bool = detect_colors(
+        env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0"),
+        io::stderr().is_terminal(),
+    );
+}
+
+static COLOR_MODE: AtomicU8 = AtomicU8::new(ColorMode::Auto as u8);
+
+/// Whether the console output is coloured.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colours when stderr is a terminal, honouring `NO_COLOR` and `CLICOLOR_FORCE` (the default).
+    Auto,
+    /// Always use colours.
+    Always,
+    /// Never use colours.
+    Never,
+}
+
+/// The colours of the `Colored` format and of the `log_trace!` output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Colours of levels that differ from the defaults, by upper-cased level name.
+    levels: Vec<(String, String)>,
+    /// The timestamp at the start of the line.
+    pub timestamp: String,
+    /// The target of records from the `log` bridge and the `tracing` layer, and the `Module:` label of `log_trace!`.
+    pub target: String,
+    /// The `file:Lline` location, and the `Timestamp:` label of `log_trace!`.
+    pub location: String,
+    /// The keys of structured fields, the traced expression, its `Type:` label and the thread of `log_trace!`.
+    pub key: String,
+    /// The traced value of `log_trace!`.
+    pub value: String,
+    /// The `->>` and `|` separators of `log_trace!` and of the `tracing` span context.
+    pub separator: String,
+    /// The type of the traced value and the context of `log_trace!` and of the `tracing` layer.
+    pub emphasis: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            levels: Vec::new(),
+            timestamp: "\x1b[1m\x1b[37m".to_string(),
+            target: "\x1b[33m".to_string(),
+            location: "\x1b[34m".to_string(),
+            key: "\x1b[32m".to_string(),
+            value: "\x1b[31m".to_string(),
+            separator: "\x1b[36m".to_string(),
+            emphasis: "\x1b[1m".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    /// Sets the colour of a built-in or custom level (e.g. `"INFO"` or `"AUDIT"`).
+    pub fn with_level(mut self, level: &str, color: &str) -> Self {
+        let level = level.to_ascii_uppercase();
+        self.levels.retain(|(name, _)| *name != level);
+        self.levels.push((level, color.to_string()));
+        self
+    }
+
+    /// Sets the colour of the timestamp.
+    pub fn with_timestamp(mut self, color: &str) -> Self {
+        self.timestamp = color.to_string();
+        self
+    }
+
+    /// Sets the colour of the target.
+    pub fn with_target(mut self, color: &str) -> Self {
+        self.target = color.to_string();
+        self
+    }
+
+    /// Sets the colour of the source location.
+    pub fn with_location(mut self, color: &str) -> Self {
+        self.location = color.to_string();
+        self
+    }
+
+    /// Sets the colour of field keys and traced expressions.
+    pub fn with_key(mut self, color: &str) -> Self {
+        self.key = color.to_string();
+        self
+    }
+
+    /// Sets the colour of traced values.
+    pub fn with_value(mut self, color: &str) -> Self {
+        self.value = color.to_string();
+        self
+    }
+
+    /// Sets the colour of the separators.
+    pub fn with_separator(mut self, color: &str) -> Self {
+        self.separator = color.to_string();
+        self
+    }
+
+    /// Sets the colour of traced types and contexts.
+    pub fn with_emphasis(mut self, color: &str) -> Self {
+        self.emphasis = color.to_string();
+        self
+    }
+
+    /// Returns the colour of the level: the one set with `with_level`, else the default one for built-in levels,
+    /// else the one given to `register_level`, else a reset.
+    pub fn level(&self, level: &str) -> String {
+        if let Some((_, color)) = self.levels.iter().find(|(name, _)| name.eq_ignore_ascii_case(level)) {
+            return color.clone();
+        }
+        let default = match Severity::from_level(level) {
+            Some(Severity::Error) => "\x1b[31m\x1b[1m", // red
+            Some(Severity::Warn) => "\x1b[33m",  // yellow
+            Some(Severity::Info) => "\x1b[36m",  // cyan
+            Some(Severity::Debug) => "\x1b[34m", // blue
+            Some(Severity::Trace) => "\x1b[35m", // idk
+            None => return level_color(level).unwrap_or_else(|| "\x1b[0m".to_string()), // reset if unknown
+        };
+        default.to_string()
+    }
+}
+
+/// Sets the colour theme.
+pub fn set_theme(theme: Theme) {
+    *write(&THEME) = theme;
+}
+
+/// Same as `set_theme`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_theme(theme: Theme) -> Result<(), LoggerError> {
+    *try_write(&THEME, "theme")? = theme;
+    Ok(())
+}
+
+/// Returns a copy of the current colour theme.
+pub fn theme() -> Theme {
+    read(&THEME).clone()
+}
+
+/// Sets whether the console output is coloured.
+pub fn set_color_mode(mode: ColorMode) {
+    COLOR_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Returns `true` if the console output is coloured, according to the `ColorMode`.
+pub fn colors_enabled() -> bool {
+    match COLOR_MODE.load(Ordering::Relaxed) {
+        mode if mode == ColorMode::Always as u8 => true,
+        mode if mode == ColorMode::Never as u8 => false,
+        _ => *AUTO_COLORS,
+    }
+}
+
+/// Decides whether to use colours in `ColorMode::Auto`: `CLICOLOR_FORCE` wins over `NO_COLOR`, which wins over the terminal check.
+fn detect_colors(no_color: bool, force: bool, terminal: bool) -> bool {
+    force || (!no_color && terminal)
+}
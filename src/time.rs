@@ -1,7 +1,133 @@
-use chrono::Local;
+//! # Timestamps
+//! Every record (console line, file line, `log_trace!` output) gets its timestamp from `current_time`,
+//! which follows the configured `TimestampFormat` and `TimestampZone`.
+//! By default it is the local `%Y-%m-%d %H:%M:%S` time.
+//! ```
+//! use logger_rust::*;
+//!
+//! set_timestamp_format(TimestampFormat::Iso8601Millis);
+//! set_timestamp_zone(TimestampZone::Utc);
+//! log_info!("Timestamped like 2023-06-09T13:06:46.123Z");
+//!
+//! set_timestamp_format(TimestampFormat::Custom("%d/%m/%Y %H:%M:%S%.3f".to_string()));
+//! set_timestamp_zone(TimestampZone::Fixed(FixedOffset::east_opt(2 * 3600).unwrap()));
+//! log_info!("Timestamped like 09/06/2023 15:06:46.123");
+//!
+//! assert!(try_set_timestamp_format(TimestampFormat::Custom("%Q".to_string())).is_err());
+//! # set_timestamp_format(TimestampFormat::Default);
+//! # set_timestamp_zone(TimestampZone::Local);
+//! ```
+use crate::error::{read, report_error, try_write, write, LoggerError};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, SecondsFormat, TimeZone, Utc,
+};
+pub use chrono::FixedOffset;
+use std::{fmt, sync::RwLock};
+
+lazy_static::lazy_static! {
+    static ref TIMESTAMP_FORMAT: RwLock<TimestampFormat> = RwLock::new(TimestampFormat::Default);
+    static ref TIMESTAMP_ZONE: RwLock<TimestampZone> = RwLock::new(TimestampZone::Local);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimestampFormat {
+/// # TimestampFormat enum
+/// - `Default` is `%Y-%m-%d %H:%M:%S` (`2023-06-09 15:06:46`);
+/// - `Rfc3339` is `2023-06-09T15:06:46+02:00` (`Z` in UTC);
+/// - `Iso8601Millis` and `Iso8601Micros` are the same with 3 or 6 fractional digits (`2023-06-09T15:06:46.123+02:00`);
+/// - `EpochMillis` is the number of milliseconds since the Unix epoch (`1686315406123`), whatever the time zone;
+/// - `Custom(pattern)` takes any `strftime` pattern supported by `chrono` (e.g. `%H:%M:%S%.3f`).
+    Default,
+    Rfc3339,
+    Iso8601Millis,
+    Iso8601Micros,
+    EpochMillis,
+    Custom(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimestampZone {
+/// # TimestampZone enum
+/// The time zone of the timestamps: the `Local` one of the machine (the default), `Utc`, or a `Fixed` offset.
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+/// Sets the format of the timestamps.
+/// An invalid `Custom` pattern is reported to the error handler and the previous format is kept.
+pub fn set_timestamp_format(format: TimestampFormat) {
+    if let Err(e) = validate(&format) {
+        report_error(e);
+        return;
+    }
+    *write(&TIMESTAMP_FORMAT) = format;
+}
+
+/// Same as `set_timestamp_format`, but returns `LoggerError::InvalidConfig` for an invalid `Custom` pattern,
+/// and fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_timestamp_format(format: TimestampFormat) -> Result<(), LoggerError> {
+    validate(&format)?;
+    *try_write(&TIMESTAMP_FORMAT, "timestamp format")? = format;
+    Ok(())
+}
+
+/// Sets the time zone of the timestamps.
+pub fn set_timestamp_zone(zone: TimestampZone) {
+    *write(&TIMESTAMP_ZONE) = zone;
+}
+
+/// Same as `set_timestamp_zone`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_timestamp_zone(zone: TimestampZone) -> Result<(), LoggerError> {
+    *try_write(&TIMESTAMP_ZONE, "timestamp zone")? = zone;
+    Ok(())
+}
+
+fn validate(format: &TimestampFormat) -> Result<(), LoggerError> {
+    match format {
+        TimestampFormat::Custom(pattern) if StrftimeItems::new(pattern).any(|item| item == Item::Error) => Err(
+            LoggerError::InvalidConfig(format!("`{}` is not a valid strftime pattern", pattern)),
+        ),
+        _ => Ok(()),
+    }
+}
 
 pub fn current_time() -> String {
 //! Determines and gets the current time for PC
-//! Nothing special here lol i just placed a 5 line code into different module
-    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+//! The time is rendered with the configured `TimestampFormat`, in the configured `TimestampZone`.
+    format_time(Utc::now(), &read(&TIMESTAMP_FORMAT))
+}
+
+/// The timestamp of the `TimestampType::Chrono` segment of `log_trace!`: the same as `current_time`,
+/// except that the default format gets microseconds.
+pub(crate) fn trace_time() -> String {
+    match &*read(&TIMESTAMP_FORMAT) {
+        TimestampFormat::Default => {
+            format_time(Utc::now(), &TimestampFormat::Custom("%Y-%m-%d %H:%M:%S%.6f".to_string()))
+        }
+        format => format_time(Utc::now(), format),
+    }
+}
+
+fn format_time(now: DateTime<Utc>, format: &TimestampFormat) -> String {
+    match *read(&TIMESTAMP_ZONE) {
+        TimestampZone::Local => render(now.with_timezone(&Local), format),
+        TimestampZone::Utc => render(now, format),
+        TimestampZone::Fixed(offset) => render(now.with_timezone(&offset), format),
+    }
+}
+
+fn render<Tz: TimeZone>(now: DateTime<Tz>, format: &TimestampFormat) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    match format {
+        TimestampFormat::Default => now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        TimestampFormat::Rfc3339 => now.to_rfc3339_opts(SecondsFormat::Secs, true),
+        TimestampFormat::Iso8601Millis => now.to_rfc3339_opts(SecondsFormat::Millis, true),
+        TimestampFormat::Iso8601Micros => now.to_rfc3339_opts(SecondsFormat::Micros, true),
+        TimestampFormat::EpochMillis => now.timestamp_millis().to_string(),
+        TimestampFormat::Custom(pattern) => now.format(pattern).to_string(),
+    }
 }
@@ -1,20 +1,32 @@
-//! It is not already used, it just an template for future using in log_trace! macro.
-/// I'll be used as a tracing configuration:
-/// ```rust
-/// log_trace_conf(LogTrace::TracerConfiguration::new(
-///   Context: enabled;
-///   Timestamp: enabled;
-///   Timestamp_Type: chrono;
-///   File: enabled;
-///   Line: enabled;
-///   Format: "{}:{} - used: {} ->> ({:?}): {:?} ->> Thread ID: {:?} Timestamp: {}{}";
-/// ));
-/// ```
+//! # Tracer configuration
+//! Controls what the `log_trace!(object)` and `log_trace!(object, context)` forms print.
+//! Every option is an `Option`; `None` keeps the default behaviour (the segment is shown, the timestamp is a Unix one).
+//! ```rust
+//! use logger_rust::*;
+//!
+//! set_tracer_config(TracerConfiguration {
+//!     timestamp_type: Some(TimestampType::Chrono),
+//!     line_enabled: Some(false),
+//!     ..TracerConfiguration::new()
+//! });
+//! let x = 42;
+//! log_trace!(x); // 2023-06-09 14:57:47 [TRACE] src/main.rs - used: x ->> (42): 42 | Type: <i32> | ThreadId(1) ->> Timestamp: 2023-06-09 14:57:47.694020 | Module: main
+//!
+//! set_tracer_config(TracerConfiguration {
+//!     format: Some("{expression} = {value} at {file}:{line}{context}".to_string()),
+//!     ..TracerConfiguration::new()
+//! });
+//! log_trace!(x, "answer"); // 2023-06-09 14:57:47 [TRACE] x = 42 at src/main.rs:11 ->> Context: <answer>
+//! # set_tracer_config(TracerConfiguration::new());
+//! ```
 use std::sync::RwLock;
 use std::time::UNIX_EPOCH;
 use std::time::SystemTime;
 
 use crate::current_time;
+use crate::time::trace_time;
+use crate::theme::theme;
+use crate::error::{read, try_write, write, LoggerError};
 
 lazy_static::lazy_static! {
     /// A global instance of `TracerConfiguration` that can be accessed and modified using the `set_tracer_config` and `get_tracer_config` functions.
@@ -27,20 +39,27 @@ lazy_static::lazy_static! {
 ///
 /// * `config` - The new tracer configuration.
 pub fn set_tracer_config(config: TracerConfiguration) {
-    *TRACER_CONFIG.write().unwrap() = config;
+    *write(&TRACER_CONFIG) = config;
+}
+
+/// Same as `set_tracer_config`, but fails with `LoggerError::Poisoned` instead of recovering a poisoned lock.
+pub fn try_set_tracer_config(config: TracerConfiguration) -> Result<(), LoggerError> {
+    *try_write(&TRACER_CONFIG, "tracer configuration")? = config;
+    Ok(())
 }
 
 /// Returns a copy of the current global tracer configuration.
 pub fn get_tracer_config() -> TracerConfiguration {
-    (*TRACER_CONFIG.read().unwrap()).clone()
+    read(&TRACER_CONFIG).clone()
 }
 
 /// An enum representing the different types of timestamps that can be used in log messages.
 #[derive(Clone, Copy)]
 pub enum TimestampType {
-    /// A timestamp in the format "YYYY-MM-DD HH:MM:SS".
+    /// A timestamp in the format "YYYY-MM-DD HH:MM:SS.ffffff", or in the format set with `set_timestamp_format`,
+    /// in the time zone set with `set_timestamp_zone` (local by default).
     Chrono,
-    /// A Unix timestamp (the number of seconds since January 1, 1970).
+    /// A Unix timestamp (the number of microseconds since January 1, 1970), rendered as `UN<micros>IX`.
     Unix,
 }
 
@@ -58,6 +77,10 @@ pub struct TracerConfiguration {
     /// Whether to include the line number in log messages. If `None`, the default behavior is used.
     pub line_enabled: Option<bool>,
     /// The format string to use when generating log messages. If `None`, the default behavior is used.
+    ///
+    /// The following placeholders are replaced: `{file}`, `{line}`, `{column}`, `{module}`, `{expression}`,
+    /// `{value}`, `{type}`, `{thread}`, `{timestamp}` and `{context}` (which already contains the ` ->> Context: <...>` prefix).
+    /// Placeholders of disabled segments are replaced with an empty string.
     pub format: Option<String>,
 }
 
@@ -68,6 +91,146 @@ impl TracerConfiguration {
     }
 }
 
+/// Everything `log_trace!` knows about the traced expression, as passed to `render_trace`.
+pub struct TraceInfo<'a> {
+    /// The file the macro was invoked in.
+    pub file: &'a str,
+    /// The line the macro was invoked on.
+    pub line: u32,
+    /// The column the macro was invoked on.
+    pub column: u32,
+    /// The module path the macro was invoked in.
+    pub module_path: &'a str,
+    /// The traced expression, as written in the source.
+    pub expression: &'a str,
+    /// The `Debug` representation of the traced value.
+    pub value: &'a str,
+    /// The type name of the traced value.
+    pub type_name: &'a str,
+    /// The context passed to `log_trace!`, empty if none.
+    pub context: &'a str,
+}
+
+/// Replaces the `{name}` placeholders of `format` in a single pass, so that substituted text is never scanned again.
+/// Unknown placeholders are kept as they are.
+fn fill_placeholders(format: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let filled = rest.find('}').and_then(|end| value(&rest[1..end]).map(|value| (value, end)));
+        match filled {
+            Some((value, end)) => {
+                out.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Renders the message of a `log_trace!` record according to the global tracer configuration.
+/// Placeholders are only looked up in the format itself, never in the traced value:
+/// ```
+/// use logger_rust::*;
+///
+/// set_tracer_config(TracerConfiguration {
+///     format: Some("{expression} = {value}".to_string()),
+///     ..TracerConfiguration::new()
+/// });
+/// let info = TraceInfo {
+///     file: "src/main.rs",
+///     line: 3,
+///     column: 5,
+///     module_path: "main",
+///     expression: "template",
+///     value: "\"{type} {thread}\"",
+///     type_name: "&str",
+///     context: "",
+/// };
+/// assert_eq!(render_trace(&info), "template = \"{type} {thread}\"");
+/// # set_tracer_config(TracerConfiguration::new());
+/// ```
+pub fn render_trace(info: &TraceInfo) -> String {
+    let config = get_tracer_config();
+    let file_enabled = config.file_enabled.unwrap_or(true);
+    let line_enabled = config.line_enabled.unwrap_or(true);
+    let timestamp_enabled = config.timestamp_enabled.unwrap_or(true);
+    let context_enabled = config.context_enabled.unwrap_or(true) && !info.context.is_empty();
+    let thread_id = format!("{:?}", std::thread::current().id());
+    let timestamp = if timestamp_enabled {
+        match config.timestamp_type.unwrap_or(TimestampType::Unix) {
+            TimestampType::Unix => format!("UN{}IX", <() as TracerConfig>::timestamp()),
+            TimestampType::Chrono => trace_time(),
+        }
+    } else {
+        String::new()
+    };
+
+    if let Some(format) = &config.format {
+        let context = if context_enabled {
+            format!(" ->> Context: <{}>", info.context)
+        } else {
+            String::new()
+        };
+        return fill_placeholders(format, |placeholder| match placeholder {
+            "file" => Some(if file_enabled { info.file.to_string() } else { String::new() }),
+            "line" => Some(if line_enabled { info.line.to_string() } else { String::new() }),
+            "column" => Some(if line_enabled { info.column.to_string() } else { String::new() }),
+            "module" => Some(info.module_path.to_string()),
+            "expression" => Some(info.expression.to_string()),
+            "value" => Some(info.value.to_string()),
+            "type" => Some(info.type_name.to_string()),
+            "thread" => Some(thread_id.clone()),
+            "timestamp" => Some(timestamp.clone()),
+            "context" => Some(context.clone()),
+            _ => None,
+        });
+    }
+
+    let location = match (file_enabled, line_enabled) {
+        (true, true) => format!("{}:L{}/C{} - ", info.file, info.line, info.column),
+        (true, false) => format!("{} - ", info.file),
+        (false, true) => format!("L{}/C{} - ", info.line, info.column),
+        (false, false) => String::new(),
+    };
+    let theme = theme();
+    let timestamp = if timestamp_enabled {
+        format!(" {}->> {}Timestamp: {}", theme.separator, theme.location, timestamp)
+    } else {
+        String::new()
+    };
+    let context = if context_enabled {
+        format!("{} ->> Context: \x1b[0m{}<{}>", theme.separator, theme.emphasis, info.context)
+    } else {
+        String::new()
+    };
+    format!(
+        "{location_color}{}used: {key}{}{separator} ->> ({}): {}{}{separator} | {key}Type: \x1b[0m{}<{}>\x1b[0m | {key}{}{}{}\x1b[0m{separator} |{} Module: \x1b[0m{}",
+        location,
+        info.expression,
+        info.value,
+        theme.value,
+        info.value,
+        theme.emphasis,
+        info.type_name,
+        thread_id,
+        timestamp,
+        context,
+        theme.target,
+        info.module_path,
+        location_color = theme.location,
+        key = theme.key,
+        separator = theme.separator,
+    )
+}
+
 /// A trait for types that can be logged using the tracer.
 pub trait Loggable {
     /// Returns a string representation of the value that can be included in a log message.
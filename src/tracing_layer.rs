@@ -0,0 +1,216 @@
+//! # tracing layer
+//! Available with the `tracing` cargo feature.
+//! `LoggerLayer` is a `tracing_subscriber::Layer` that renders `tracing` events through `log_record`,
+//! so they get the same coloured format, log files and sinks as `log_info!` and friends.
+//! Event fields are kept as typed structured fields of the record, while the names and fields of the spans
+//! the event happened in are appended as a `->> Context: <...>` segment, the same way `log_trace!` renders its context.
+//!
+//! ```
+//! use logger_rust::*;
+//! use tracing_subscriber::layer::SubscriberExt;
+//!
+//! let subscriber = tracing_subscriber::registry().with(LoggerLayer::new());
+//! tracing::subscriber::with_default(subscriber, || {
+//!     let span = tracing::info_span!("request", id = 7);
+//!     let _guard = span.enter();
+//!     tracing::info!(user = "alice", "logged in");
+//!     // 2023-06-09 15:06:46 [INFO] my_app src/main.rs:L12 - logged in ->> Context: <request{id=7}> user=alice
+//! });
+//! ```
+//!
+//! The severity threshold and the filter only apply to this layer, the other layers of the subscriber still see every event:
+//! ```
+//! use logger_rust::*;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+//!
+//! static SEEN: AtomicUsize = AtomicUsize::new(0);
+//!
+//! struct CountingLayer;
+//!
+//! impl<S: tracing::Subscriber> Layer<S> for CountingLayer {
+//!     fn on_event(&self, _event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+//!         SEEN.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! fn poll() {
+//!     tracing::debug!("polling");
+//! }
+//!
+//! let subscriber = tracing_subscriber::registry().with(LoggerLayer::new()).with(CountingLayer);
+//! tracing::subscriber::with_default(subscriber, || {
+//!     set_min_severity(Severity::Info);
+//!     poll(); // dropped by LoggerLayer only
+//!     assert_eq!(SEEN.load(Ordering::Relaxed), 1);
+//!
+//!     set_min_severity(Severity::Trace);
+//!     poll(); // logged by LoggerLayer as well
+//!     assert_eq!(SEEN.load(Ordering::Relaxed), 2);
+//! });
+//! ```
+use crate::config::Severity;
+use crate::filter::module_enabled;
+use crate::log_file::log_record;
+use crate::record::{Record, Value};
+use crate::theme::theme;
+use crate::time::current_time;
+use std::fmt::{self, Write};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// A `tracing_subscriber::Layer` that forwards events to this logger.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggerLayer;
+
+impl LoggerLayer {
+    /// Creates a new `LoggerLayer`.
+    pub fn new() -> Self {
+        LoggerLayer
+    }
+}
+
+/// The rendered fields of a span, stored in the span extensions.
+struct SpanFields(String);
+
+/// Collects the `message` field and keeps every other field as a typed `Value`.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<(String, Value)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name().to_string(), Value::Int(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.push((field.name().to_string(), Value::UInt(value)));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.push((field.name().to_string(), Value::Float(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.push((field.name().to_string(), Value::Bool(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields.push((field.name().to_string(), Value::from(value)));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.fields.push((field.name().to_string(), Value::Debug(format!("{:?}", value))));
+        }
+    }
+}
+
+impl FieldVisitor {
+    /// Renders the collected fields as `key=value key=value`, used for the span context.
+    fn render_fields(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Maps a `tracing::Level` to the matching `Severity`.
+fn severity_of(level: &Level) -> Severity {
+    match *level {
+        Level::ERROR => Severity::Error,
+        Level::WARN => Severity::Warn,
+        Level::INFO => Severity::Info,
+        Level::DEBUG => Severity::Debug,
+        _ => Severity::Trace,
+    }
+}
+
+impl<S> Layer<S> for LoggerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // Never veto an event: `enabled` and `Interest::never` would hide it from every other layer as well,
+    // and for good, since the interest is cached. `on_event` checks the severity and the filter instead.
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.render_fields()));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &tracing::span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            values.record(&mut visitor);
+            let mut extensions = span.extensions_mut();
+            let rendered = visitor.render_fields();
+            match extensions.get_mut::<SpanFields>() {
+                Some(SpanFields(fields)) if !fields.is_empty() => {
+                    fields.push(' ');
+                    fields.push_str(&rendered);
+                }
+                Some(SpanFields(fields)) => *fields = rendered,
+                None => extensions.insert(SpanFields(rendered)),
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let severity = severity_of(metadata.level());
+        if !module_enabled(severity, metadata.target()) {
+            return;
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut context = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if !context.is_empty() {
+                    context.push(':');
+                }
+                context.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    if !fields.is_empty() {
+                        let _ = write!(context, "{{{}}}", fields);
+                    }
+                }
+            }
+        }
+
+        let mut message = visitor.message;
+        if !context.is_empty() {
+            let theme = theme();
+            let _ = write!(message, "{} ->> Context: \x1b[0m{}<{}>", theme.separator, theme.emphasis, context);
+        }
+
+        let mut record = Record::new(severity.as_str(), &current_time(), &message);
+        record.target = Some(metadata.target().to_string());
+        record.module_path = metadata.module_path().map(str::to_string);
+        record.file = metadata.file().map(str::to_string);
+        record.line = metadata.line();
+        record.fields = visitor.fields;
+        log_record(record);
+    }
+}